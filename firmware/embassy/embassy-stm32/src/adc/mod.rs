@@ -14,6 +14,9 @@
 #[cfg_attr(adc_v4, path = "v4.rs")]
 mod _version;
 
+#[cfg(any(adc_v1, adc_l0))]
+mod ringbuffered_v1;
+
 #[cfg(not(any(adc_f1, adc_f3_v2)))]
 mod resolution;
 mod sample_time;
@@ -23,6 +26,8 @@ mod sample_time;
 pub use _version::*;
 #[cfg(not(any(adc_f1, adc_f3, adc_f3_v2)))]
 pub use resolution::Resolution;
+#[cfg(any(adc_v1, adc_l0))]
+pub use ringbuffered_v1::*;
 #[cfg(not(adc_f3_v2))]
 pub use sample_time::SampleTime;
 
@@ -90,6 +95,8 @@ pub trait AdcPin<T: Instance>: sealed::AdcPin<T> {}
 /// ADC internal channel.
 pub trait InternalChannel<T>: sealed::InternalChannel<T> {}
 
+dma_trait!(RxDma, Instance);
+
 foreach_adc!(
     ($inst:ident, $common_inst:ident, $clock:ident) => {
         impl crate::adc::sealed::Instance for peripherals::$inst {
@@ -0,0 +1,196 @@
+use core::marker::PhantomData;
+
+use embassy_hal_internal::into_ref;
+use stm32_metapac::adc::vals::{Dmacfg, Exten, Ovrmod, Scandir};
+
+use super::_version::set_watchdog_window;
+use crate::adc::{Adc, AdcPin, Instance, RxDma};
+use crate::dma::ReadableRingBuffer;
+use crate::Peripheral;
+
+/// Channels that are converted one after the other on every trigger.
+///
+/// The ADC always converts them in ascending order of their channel number, independent of
+/// the order they are added in, e.g. PA0 (channel 0) before VREFINT (channel 17).
+pub struct Sequence<T: Instance> {
+    channels: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> Sequence<T> {
+    pub const fn new() -> Self {
+        Self {
+            channels: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Adds the channel of `pin` to the sequence.
+    pub fn add(&mut self, pin: &mut impl AdcPin<T>) -> &mut Self {
+        pin.set_as_analog();
+        self.channels |= 1 << pin.channel();
+        self
+    }
+
+    /// Number of conversions per trigger.
+    pub fn len(&self) -> usize {
+        self.channels.count_ones() as usize
+    }
+
+    /// Position of the result of `pin` in every sequence of the buffer, `None` if its channel
+    /// is not part of the sequence.
+    pub fn index(&self, pin: &impl AdcPin<T>) -> Option<usize> {
+        let bit = 1u32 << pin.channel();
+        (self.channels & bit != 0).then(|| (self.channels & (bit - 1)).count_ones() as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels == 0
+    }
+}
+
+impl<T: Instance> Default for Sequence<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hardware event that starts a conversion sequence.
+#[derive(Clone, Copy)]
+pub struct Trigger {
+    /// Trigger source, the mapping of the EXTSEL values to timers and EXTI lines is device
+    /// specific, e.g. 6 is TIM3_TRGO on the L0.
+    pub extsel: u8,
+    pub edge: Exten,
+}
+
+/// The reader didn't keep up and samples were overwritten. The conversions were restarted
+/// at the beginning of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OverrunError;
+
+/// Continuous, hardware-triggered conversion of a sequence of channels into a DMA ring buffer.
+///
+/// Created with [Adc::into_ring_buffered].
+pub struct RingBufferedAdc<'d, T: Instance, D: RxDma<T>> {
+    _adc: Adc<'d, T>,
+    ring_buf: ReadableRingBuffer<'d, D, u16>,
+    sequence_len: usize,
+}
+
+impl<'d, T: Instance> Adc<'d, T> {
+    /// Converts `sequence` on every `trigger` and writes the results to `dma_buf` with a
+    /// circular DMA transfer.
+    ///
+    /// The length of `dma_buf` has to be a multiple of the sequence length, so every
+    /// sequence starts at the same positions in the buffer. The sample time, resolution,
+    /// oversampling and analog watchdog settings of the `Adc` are kept.
+    pub fn into_ring_buffered<D: RxDma<T>>(
+        self,
+        dma: impl Peripheral<P = D> + 'd,
+        dma_buf: &'d mut [u16],
+        sequence: &Sequence<T>,
+        trigger: Trigger,
+    ) -> RingBufferedAdc<'d, T, D> {
+        into_ref!(dma);
+        let sequence_len = sequence.len();
+        assert!(sequence_len > 0 && dma_buf.len() % sequence_len == 0);
+
+        // the configuration can only be changed while no conversion is ongoing
+        while T::regs().cr().read().adstart() {}
+
+        T::regs().chselr().write(|reg| {
+            for channel in 0..32 {
+                if sequence.channels & (1 << channel) != 0 {
+                    reg.set_chselx(channel, true);
+                }
+            }
+        });
+        T::regs().smpr().modify(|reg| reg.set_smp(self.sample_time.into()));
+        T::regs().cfgr1().modify(|reg| {
+            reg.set_scandir(Scandir::UPWARD);
+            reg.set_cont(false);
+            reg.set_extsel(trigger.extsel);
+            reg.set_exten(trigger.edge);
+            // keep converting if the DMA is late, the reader detects the overrun
+            reg.set_ovrmod(Ovrmod::OVERWRITTEN);
+            reg.set_dmacfg(Dmacfg::CIRCULAR);
+            reg.set_dmaen(true);
+        });
+
+        let request = dma.request();
+        let opts = Default::default();
+        let ring_buf =
+            unsafe { ReadableRingBuffer::new(dma, request, T::regs().dr().as_ptr() as *mut u16, dma_buf, opts) };
+
+        RingBufferedAdc {
+            _adc: self,
+            ring_buf,
+            sequence_len,
+        }
+    }
+}
+
+impl<'d, T: Instance, D: RxDma<T>> RingBufferedAdc<'d, T, D> {
+    /// Starts the DMA transfer and arms the trigger.
+    pub fn start(&mut self) {
+        self.ring_buf.restart();
+        T::regs().cr().modify(|reg| reg.set_adstart(true));
+    }
+
+    /// Stops the conversions after the current one.
+    pub fn stop(&mut self) {
+        if T::regs().cr().read().adstart() {
+            T::regs().cr().modify(|reg| reg.set_adstp(true));
+            while T::regs().cr().read().adstp() {}
+        }
+    }
+
+    /// Number of conversions per trigger.
+    pub fn sequence_len(&self) -> usize {
+        self.sequence_len
+    }
+
+    /// Waits until `buf` is filled with complete sequences, its length has to be a multiple
+    /// of the sequence length.
+    ///
+    /// The DMA wakes the reader when half of the ring buffer is filled, so reading half of
+    /// the buffer at a time gives the shortest latency. After an overrun the conversions are
+    /// restarted and the next read returns new samples.
+    pub async fn read(&mut self, buf: &mut [u16]) -> Result<(), OverrunError> {
+        assert!(buf.len() % self.sequence_len == 0);
+
+        match self.ring_buf.read_exact(buf).await {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                self.stop();
+                self.start();
+                Err(OverrunError)
+            }
+        }
+    }
+
+    /// Moves the window of the analog watchdog and re-arms its interrupt, see
+    /// [Adc::set_watchdog]. The channels are selected before the conversions are started,
+    /// the window can be moved while they are running.
+    pub fn set_watchdog_window(&mut self, low: u16, high: u16) {
+        set_watchdog_window::<T>(low, high);
+    }
+
+    /// Returns `true` if a conversion was outside the watchdog window since the window was
+    /// last set.
+    pub fn watchdog_triggered(&self) -> bool {
+        T::regs().isr().read().awd()
+    }
+}
+
+impl<'d, T: Instance, D: RxDma<T>> Drop for RingBufferedAdc<'d, T, D> {
+    fn drop(&mut self) {
+        self.stop();
+        T::regs().cfgr1().modify(|reg| {
+            reg.set_exten(Exten::DISABLED);
+            reg.set_dmaen(false);
+        });
+    }
+}
@@ -4,6 +4,7 @@ use core::task::Poll;
 
 use embassy_hal_internal::into_ref;
 use embedded_hal_02::blocking::delay::DelayUs;
+use stm32_metapac::adc::vals::Awdsgl;
 #[cfg(adc_l0)]
 use stm32_metapac::adc::vals::Ckmode;
 
@@ -22,16 +23,52 @@ pub struct InterruptHandler<T: Instance> {
 
 impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
     unsafe fn on_interrupt() {
-        if T::regs().isr().read().eoc() {
+        let isr = T::regs().isr().read();
+        let ier = T::regs().ier().read();
+
+        // the watchdog flag stays set until the watchdog is configured again, so the
+        // application can still see it; only the interrupt is disabled
+        if isr.awd() && ier.awdie() {
+            T::regs().ier().modify(|w| w.set_awdie(false));
+        }
+
+        if isr.eoc() && ier.eocie() {
             T::regs().ier().modify(|w| w.set_eocie(false));
-        } else {
-            return;
+            T::state().waker.wake();
         }
+    }
+}
+
+/// Channels that are compared against the analog watchdog window.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogChannels {
+    /// Every converted channel.
+    All,
+    /// Only the channel with this number.
+    Single(u8),
+}
 
-        T::state().waker.wake();
+impl WatchdogChannels {
+    /// Only the channel of `pin`.
+    pub fn pin<T: Instance>(pin: &impl AdcPin<T>) -> Self {
+        WatchdogChannels::Single(pin.channel())
     }
 }
 
+/// Oversampling ratio, the number of conversions that are summed up for one result.
+#[cfg(adc_l0)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OversamplingRatio {
+    X2 = 0,
+    X4 = 1,
+    X8 = 2,
+    X16 = 3,
+    X32 = 4,
+    X64 = 5,
+    X128 = 6,
+    X256 = 7,
+}
+
 #[cfg(not(adc_l0))]
 pub struct Vbat;
 
@@ -55,12 +92,21 @@ impl super::sealed::AdcPin<ADC> for Vref {
 
 pub struct Temperature;
 impl AdcPin<ADC> for Temperature {}
+#[cfg(not(adc_l0))]
 impl super::sealed::AdcPin<ADC> for Temperature {
     fn channel(&self) -> u8 {
         16
     }
 }
 
+// ADC_IN18 on L0, channel 16 is not connected there
+#[cfg(adc_l0)]
+impl super::sealed::AdcPin<ADC> for Temperature {
+    fn channel(&self) -> u8 {
+        18
+    }
+}
+
 impl<'d, T: Instance> Adc<'d, T> {
     pub fn new(
         adc: impl Peripheral<P = T> + 'd,
@@ -150,6 +196,89 @@ impl<'d, T: Instance> Adc<'d, T> {
         T::regs().cfgr1().modify(|reg| reg.set_res(resolution.into()));
     }
 
+    /// Enables the analog watchdog.
+    ///
+    /// A conversion of a monitored channel with a result below `low` or above `high`
+    /// (raw 12-bit codes) sets the watchdog flag and raises the ADC interrupt. Additional
+    /// interrupt handlers bound to the ADC interrupt run in the same interrupt, so they can
+    /// react within the conversion time. The interrupt is disabled after the first event
+    /// until the watchdog is configured again.
+    pub fn set_watchdog(&mut self, channels: WatchdogChannels, low: u16, high: u16) {
+        // the configuration can only be changed while no conversion is ongoing
+        while T::regs().cr().read().adstart() {}
+
+        T::regs().cfgr1().modify(|reg| {
+            match channels {
+                WatchdogChannels::All => reg.set_awdsgl(Awdsgl::ALLCHANNELS),
+                WatchdogChannels::Single(channel) => {
+                    reg.set_awdsgl(Awdsgl::SINGLECHANNEL);
+                    reg.set_awdch(channel);
+                }
+            }
+            reg.set_awden(true);
+        });
+
+        set_watchdog_window::<T>(low, high);
+    }
+
+    /// Disables the analog watchdog and clears its flag.
+    pub fn disable_watchdog(&mut self) {
+        while T::regs().cr().read().adstart() {}
+
+        T::regs().ier().modify(|reg| reg.set_awdie(false));
+        T::regs().cfgr1().modify(|reg| reg.set_awden(false));
+        T::regs().isr().write(|reg| reg.set_awd(true));
+    }
+
+    /// Returns `true` if a conversion was outside the watchdog window since the watchdog
+    /// was configured.
+    pub fn watchdog_triggered(&self) -> bool {
+        T::regs().isr().read().awd()
+    }
+
+    /// Enables the hardware oversampler.
+    ///
+    /// Every result is the sum of `ratio` conversions shifted right by `shift` bits (0 to 8),
+    /// so without a shift a ratio of 16 gives a 16-bit result. The analog watchdog compares
+    /// the 12 most significant bits of the 16-bit result.
+    #[cfg(adc_l0)]
+    pub fn set_oversampling(&mut self, ratio: OversamplingRatio, shift: u8) {
+        assert!(shift <= 8);
+        self.reconfigure(|| {
+            T::regs().cfgr2().modify(|reg| {
+                reg.set_ovsr(ratio as u8);
+                reg.set_ovss(shift);
+                reg.set_tovs(false);
+                reg.set_ovse(true);
+            })
+        });
+    }
+
+    /// Disables the hardware oversampler.
+    #[cfg(adc_l0)]
+    pub fn disable_oversampling(&mut self) {
+        self.reconfigure(|| T::regs().cfgr2().modify(|reg| reg.set_ovse(false)));
+    }
+
+    // the oversampler can only be configured while the ADC is disabled
+    #[cfg(adc_l0)]
+    fn reconfigure(&mut self, f: impl FnOnce()) {
+        // A.7.3 ADC disable code example
+        if T::regs().cr().read().adstart() {
+            T::regs().cr().modify(|reg| reg.set_adstp(true));
+            while T::regs().cr().read().adstp() {}
+        }
+        T::regs().cr().modify(|reg| reg.set_addis(true));
+        while T::regs().cr().read().aden() {}
+
+        f();
+
+        // A.7.2 ADC enable sequence code example
+        T::regs().isr().write(|reg| reg.set_adrdy(true));
+        T::regs().cr().modify(|reg| reg.set_aden(true));
+        while !T::regs().isr().read().adrdy() {}
+    }
+
     #[cfg(adc_l0)]
     pub fn set_ckmode(&mut self, ckmode: Ckmode) {
         // set ADC clock mode
@@ -167,7 +296,9 @@ impl<'d, T: Instance> Adc<'d, T> {
     }
 
     async fn convert(&mut self) -> u16 {
-        T::regs().isr().modify(|reg| {
+        // the flags are cleared by writing 1, a read-modify-write would also clear the
+        // analog watchdog flag
+        T::regs().isr().write(|reg| {
             reg.set_eoc(true);
             reg.set_eosmp(true);
         });
@@ -191,6 +322,16 @@ impl<'d, T: Instance> Adc<'d, T> {
     }
 }
 
+// the window can also be moved while conversions are running
+pub(super) fn set_watchdog_window<T: Instance>(low: u16, high: u16) {
+    T::regs().tr().write(|reg| {
+        reg.set_lt(low.min(0xfff));
+        reg.set_ht(high.min(0xfff));
+    });
+    T::regs().isr().write(|reg| reg.set_awd(true));
+    T::regs().ier().modify(|reg| reg.set_awdie(true));
+}
+
 impl<'d, T: Instance> Drop for Adc<'d, T> {
     fn drop(&mut self) {
         // A.7.3 ADC disable code example
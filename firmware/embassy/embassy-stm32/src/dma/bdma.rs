@@ -506,6 +506,23 @@ impl<'a, C: Channel, W: Word> ReadableRingBuffer<'a, C, W> {
         self.ringbuf.clear(&mut DmaCtrlImpl(self.channel.reborrow()));
     }
 
+    /// Stop the transfer and start it again at the beginning of the buffer.
+    ///
+    /// All data that wasn't read yet is dropped. Unlike [`clear`](Self::clear) this keeps
+    /// the positions of the DMA and the reader in step, which matters when the buffer holds
+    /// fixed size records, e.g. after an overrun.
+    pub fn restart(&mut self) {
+        self.request_stop();
+        while self.is_running() {}
+
+        let len = self.ringbuf.cap();
+        let ch = self.channel.regs().ch(self.channel.num());
+        ch.ndtr().write(|w| w.set_ndt(len as u16));
+        self.clear_irqs();
+        self.clear();
+        self.start();
+    }
+
     /// Read elements from the ring buffer
     /// Return a tuple of the length read and the length remaining in the buffer
     /// If not all of the elements were read, then there will be some elements in the buffer remaining
@@ -0,0 +1,106 @@
+//! Data EEPROM of the STM32L0 family.
+//!
+//! The data EEPROM is mapped at [`EEPROM_BASE`] and can be written with byte, half-word
+//! or word granularity. An erase before programming is not necessary, the hardware erases
+//! the addressed location automatically.
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{fence, Ordering};
+
+use embassy_hal_internal::drop::OnDrop;
+
+use super::{common, family, Error, Flash};
+
+/// Absolute base address of the data EEPROM.
+pub const EEPROM_BASE: usize = 0x0808_0000;
+
+/// Size of the data EEPROM in bytes.
+#[cfg(any(stm32l07x, stm32l08x))]
+pub const EEPROM_SIZE: usize = 6 * 1024;
+/// Size of the data EEPROM in bytes.
+#[cfg(any(stm32l05x, stm32l06x))]
+pub const EEPROM_SIZE: usize = 2 * 1024;
+/// Size of the data EEPROM in bytes.
+#[cfg(any(stm32l03x, stm32l04x))]
+pub const EEPROM_SIZE: usize = 1024;
+
+impl<'d, MODE> Flash<'d, MODE> {
+    /// Blocking read from the data EEPROM.
+    ///
+    /// NOTE: `offset` is an offset from the EEPROM start, NOT an absolute address.
+    /// For example, to read address `0x0808_0010` you have to use offset `0x10`.
+    pub fn eeprom_read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        common::blocking_read(EEPROM_BASE as u32, EEPROM_SIZE as u32, offset, bytes)
+    }
+
+    /// Blocking write to the data EEPROM.
+    ///
+    /// Aligned words are programmed in one operation, everything else byte by byte.
+    /// Locations that already contain the requested value are not written again
+    /// to save write cycles.
+    ///
+    /// NOTE: `offset` is an offset from the EEPROM start, NOT an absolute address.
+    /// For example, to write address `0x0808_0010` you have to use offset `0x10`.
+    pub fn eeprom_write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        if offset + bytes.len() as u32 > EEPROM_SIZE as u32 {
+            return Err(Error::Size);
+        }
+
+        let mut address = EEPROM_BASE as u32 + offset;
+        trace!("Writing {} bytes to EEPROM at 0x{:x}", bytes.len(), address);
+
+        unsafe {
+            family::clear_all_err();
+            fence(Ordering::SeqCst);
+            family::unlock();
+            fence(Ordering::SeqCst);
+
+            let _on_drop = OnDrop::new(|| {
+                fence(Ordering::SeqCst);
+                family::lock();
+            });
+
+            let mut bytes = bytes;
+            while !bytes.is_empty() {
+                if address % 4 == 0 && bytes.len() >= 4 {
+                    let val = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+                    if read_volatile(address as *const u32) != val {
+                        write_volatile(address as *mut u32, val);
+                        family::wait_ready_blocking()?;
+                    }
+                    address += 4;
+                    bytes = &bytes[4..];
+                } else {
+                    let val = bytes[0];
+                    if read_volatile(address as *const u8) != val {
+                        write_volatile(address as *mut u8, val);
+                        family::wait_ready_blocking()?;
+                    }
+                    address += 1;
+                    bytes = &bytes[1..];
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Blocking erase of a range of the data EEPROM.
+    ///
+    /// Erased locations read as `0x00`.
+    ///
+    /// NOTE: `from` and `to` are offsets from the EEPROM start, NOT an absolute address.
+    pub fn eeprom_erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        if from > to || to > EEPROM_SIZE as u32 {
+            return Err(Error::Size);
+        }
+
+        let zeros = [0u8; 4];
+        let mut offset = from;
+        while offset < to {
+            let len = (4 - offset % 4).min(to - offset);
+            self.eeprom_write(offset, &zeros[..len as usize])?;
+            offset += len;
+        }
+        Ok(())
+    }
+}
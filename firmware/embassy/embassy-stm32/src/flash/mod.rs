@@ -5,11 +5,15 @@ use embedded_storage::nor_flash::{NorFlashError, NorFlashErrorKind};
 mod asynch;
 #[cfg(flash)]
 mod common;
+#[cfg(all(flash_l0, any(stm32l03x, stm32l04x, stm32l05x, stm32l06x, stm32l07x, stm32l08x)))]
+mod eeprom;
 
 #[cfg(flash_f4)]
 pub use asynch::InterruptHandler;
 #[cfg(flash)]
 pub use common::*;
+#[cfg(all(flash_l0, any(stm32l03x, stm32l04x, stm32l05x, stm32l06x, stm32l07x, stm32l08x)))]
+pub use eeprom::{EEPROM_BASE, EEPROM_SIZE};
 
 pub use crate::_generated::flash_regions::*;
 pub use crate::_generated::MAX_ERASE_SIZE;
@@ -0,0 +1,470 @@
+//! USBTMC class implementation with the USB488 subclass, aka USB Test & Measurement Class.
+//!
+//! Messages are exchanged on a pair of bulk endpoints, every transfer starts with a 12 byte
+//! header. The host sends commands in device dependent messages and asks for the answer with a
+//! separate request, both are handed to the application by [`UsbTmcClass::read_message`].
+//!
+//! The interface has no interrupt endpoint, so `READ_STATUS_BYTE` is answered in the control
+//! transfer and service requests are not supported.
+
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use core::task::Poll;
+
+use embassy_futures::select::{select, Either};
+use embassy_sync::waitqueue::WakerRegistration;
+
+use crate::control::{InResponse, Recipient, Request, RequestType};
+use crate::driver::{Driver, Endpoint, EndpointError, EndpointIn, EndpointOut};
+use crate::types::InterfaceNumber;
+use crate::{Builder, Handler};
+
+/// Application specific interface class
+const USB_CLASS_APPLICATION_SPECIFIC: u8 = 0xfe;
+const USBTMC_SUBCLASS: u8 = 0x03;
+const USBTMC_PROTOCOL_USB488: u8 = 0x01;
+
+const MSG_DEV_DEP_MSG_OUT: u8 = 1;
+const MSG_REQUEST_DEV_DEP_MSG_IN: u8 = 2;
+const MSG_DEV_DEP_MSG_IN: u8 = 2;
+
+const HEADER_LEN: usize = 12;
+const MAX_PACKET_SIZE: usize = 64;
+const CAPABILITIES_LEN: usize = 24;
+
+const REQ_INITIATE_ABORT_BULK_OUT: u8 = 1;
+const REQ_CHECK_ABORT_BULK_OUT_STATUS: u8 = 2;
+const REQ_INITIATE_ABORT_BULK_IN: u8 = 3;
+const REQ_CHECK_ABORT_BULK_IN_STATUS: u8 = 4;
+const REQ_INITIATE_CLEAR: u8 = 5;
+const REQ_CHECK_CLEAR_STATUS: u8 = 6;
+const REQ_GET_CAPABILITIES: u8 = 7;
+const REQ_READ_STATUS_BYTE: u8 = 128;
+
+const STATUS_SUCCESS: u8 = 0x01;
+const STATUS_PENDING: u8 = 0x02;
+const STATUS_TRANSFER_NOT_IN_PROGRESS: u8 = 0x81;
+
+/// Internal state for USBTMC
+pub struct State<'a> {
+    control: MaybeUninit<Control<'a>>,
+    shared: ControlShared,
+}
+
+impl<'a> Default for State<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> State<'a> {
+    /// Create a new `State`.
+    pub fn new() -> Self {
+        Self {
+            control: MaybeUninit::uninit(),
+            shared: ControlShared::default(),
+        }
+    }
+}
+
+/// A message from the host, see [`UsbTmcClass::read_message`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// Device dependent message with `len` bytes in the buffer. `eom` is set on the last part of a
+    /// message the host split into several transfers.
+    Data {
+        /// Number of bytes written to the buffer
+        len: usize,
+        /// End of message
+        eom: bool,
+    },
+    /// The host waits for an answer, which is sent with [`UsbTmcClass::write_message`].
+    ReadRequest(ReadRequest),
+    /// The host cleared the device, the application has to drop the input it collected and the
+    /// answers it didn't send yet.
+    Clear,
+}
+
+/// Request of the host to read an answer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReadRequest {
+    tag: u8,
+    max_len: u32,
+}
+
+impl ReadRequest {
+    /// Gets the maximum number of bytes the host accepts in this answer.
+    pub fn max_len(&self) -> usize {
+        self.max_len as usize
+    }
+}
+
+/// Packet level implementation of a USBTMC device with the USB488 subclass.
+///
+/// The class takes care of the message headers, the application only sees the data of the
+/// messages. `read_message` must be called with a buffer large enough for the longest command.
+pub struct UsbTmcClass<'d, D: Driver<'d>> {
+    read_ep: D::EndpointOut,
+    write_ep: D::EndpointIn,
+    control: &'d ControlShared,
+}
+
+struct Control<'a> {
+    iface: InterfaceNumber,
+    read_ep: u8,
+    write_ep: u8,
+    shared: &'a ControlShared,
+}
+
+/// Shared data between Control and UsbTmcClass
+struct ControlShared {
+    status_byte: AtomicU8,
+    // a device clear was requested and the class hasn't dropped its state yet
+    clear: AtomicBool,
+    // tag of the transfer in progress in each direction, 0 if there is none
+    read_tag: AtomicU8,
+    write_tag: AtomicU8,
+    // an abort of the transfer in progress was requested
+    abort_read: AtomicBool,
+    abort_write: AtomicBool,
+    // data bytes received and sent in the last transfer
+    read_count: AtomicU32,
+    write_count: AtomicU32,
+
+    waker: RefCell<WakerRegistration>,
+}
+
+impl Default for ControlShared {
+    fn default() -> Self {
+        ControlShared {
+            status_byte: AtomicU8::new(0),
+            clear: AtomicBool::new(false),
+            read_tag: AtomicU8::new(0),
+            write_tag: AtomicU8::new(0),
+            abort_read: AtomicBool::new(false),
+            abort_write: AtomicBool::new(false),
+            read_count: AtomicU32::new(0),
+            write_count: AtomicU32::new(0),
+            waker: RefCell::new(WakerRegistration::new()),
+        }
+    }
+}
+
+impl ControlShared {
+    fn wake(&self) {
+        self.waker.borrow_mut().wake();
+    }
+
+    // waits for a clear or an abort of the transfer on the bulk OUT endpoint
+    async fn read_interrupted(&self) {
+        poll_fn(|cx| {
+            if self.clear.load(Ordering::Relaxed) || self.abort_read.load(Ordering::Relaxed) {
+                Poll::Ready(())
+            } else {
+                self.waker.borrow_mut().register(cx.waker());
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+
+    // the handler and the class run in the same executor, there is no race between the two
+    fn take(flag: &AtomicBool) -> bool {
+        let set = flag.load(Ordering::Relaxed);
+        flag.store(false, Ordering::Relaxed);
+        set
+    }
+}
+
+impl<'d> Handler for Control<'d> {
+    fn reset(&mut self) {
+        let shared = self.shared;
+        shared.status_byte.store(0, Ordering::Relaxed);
+        shared.clear.store(true, Ordering::Relaxed);
+        shared.read_tag.store(0, Ordering::Relaxed);
+        shared.write_tag.store(0, Ordering::Relaxed);
+        shared.abort_read.store(false, Ordering::Relaxed);
+        shared.abort_write.store(false, Ordering::Relaxed);
+        shared.wake();
+    }
+
+    fn control_in<'a>(&'a mut self, req: Request, buf: &'a mut [u8]) -> Option<InResponse<'a>> {
+        let ours = match req.recipient {
+            Recipient::Interface => req.index == self.iface.0 as u16,
+            Recipient::Endpoint => req.index == self.read_ep as u16 || req.index == self.write_ep as u16,
+            _ => false,
+        };
+        if req.request_type != RequestType::Class || !ours {
+            return None;
+        }
+        let shared = self.shared;
+
+        let len = match (req.recipient, req.request) {
+            (Recipient::Interface, REQ_GET_CAPABILITIES) => {
+                buf[..CAPABILITIES_LEN].fill(0);
+                buf[0] = STATUS_SUCCESS;
+                // bcdUSBTMC 1.00, no indicator pulse, no TermChar
+                buf[2..4].copy_from_slice(&0x0100u16.to_le_bytes());
+                // bcdUSB488 1.00, only READ_STATUS_BYTE
+                buf[12..14].copy_from_slice(&0x0100u16.to_le_bytes());
+                CAPABILITIES_LEN
+            }
+            (Recipient::Interface, REQ_READ_STATUS_BYTE) => {
+                buf[0] = STATUS_SUCCESS;
+                buf[1] = req.value as u8;
+                buf[2] = shared.status_byte.load(Ordering::Relaxed);
+                3
+            }
+            (Recipient::Interface, REQ_INITIATE_CLEAR) => {
+                debug!("USBTMC clear");
+                shared.clear.store(true, Ordering::Relaxed);
+                shared.wake();
+                buf[0] = STATUS_SUCCESS;
+                1
+            }
+            (Recipient::Interface, REQ_CHECK_CLEAR_STATUS) => {
+                let pending = shared.clear.load(Ordering::Relaxed);
+                buf[0] = if pending { STATUS_PENDING } else { STATUS_SUCCESS };
+                // bmClear D0: the host has to read the rest of an answer
+                buf[1] = (pending && shared.write_tag.load(Ordering::Relaxed) != 0) as u8;
+                2
+            }
+            (Recipient::Endpoint, REQ_INITIATE_ABORT_BULK_OUT) if req.index == self.read_ep as u16 => {
+                let tag = req.value as u8;
+                buf[0] = if tag != 0 && shared.read_tag.load(Ordering::Relaxed) == tag {
+                    shared.abort_read.store(true, Ordering::Relaxed);
+                    shared.wake();
+                    STATUS_SUCCESS
+                } else {
+                    STATUS_TRANSFER_NOT_IN_PROGRESS
+                };
+                buf[1] = tag;
+                2
+            }
+            (Recipient::Endpoint, REQ_CHECK_ABORT_BULK_OUT_STATUS) if req.index == self.read_ep as u16 => {
+                let pending = shared.abort_read.load(Ordering::Relaxed);
+                buf[..8].fill(0);
+                buf[0] = if pending { STATUS_PENDING } else { STATUS_SUCCESS };
+                buf[4..8].copy_from_slice(&shared.read_count.load(Ordering::Relaxed).to_le_bytes());
+                8
+            }
+            (Recipient::Endpoint, REQ_INITIATE_ABORT_BULK_IN) if req.index == self.write_ep as u16 => {
+                let tag = req.value as u8;
+                buf[0] = if tag != 0 && shared.write_tag.load(Ordering::Relaxed) == tag {
+                    shared.abort_write.store(true, Ordering::Relaxed);
+                    STATUS_SUCCESS
+                } else {
+                    STATUS_TRANSFER_NOT_IN_PROGRESS
+                };
+                buf[1] = tag;
+                2
+            }
+            (Recipient::Endpoint, REQ_CHECK_ABORT_BULK_IN_STATUS) if req.index == self.write_ep as u16 => {
+                let pending = shared.abort_write.load(Ordering::Relaxed);
+                buf[..8].fill(0);
+                buf[0] = if pending { STATUS_PENDING } else { STATUS_SUCCESS };
+                // bmAbortBulkIn D0: the host has to read until the short packet that ends the transfer
+                buf[1] = pending as u8;
+                buf[4..8].copy_from_slice(&shared.write_count.load(Ordering::Relaxed).to_le_bytes());
+                8
+            }
+            _ => return Some(InResponse::Rejected),
+        };
+        Some(InResponse::Accepted(&buf[..len]))
+    }
+}
+
+impl<'d, D: Driver<'d>> UsbTmcClass<'d, D> {
+    /// Creates a new UsbTmcClass with the provided UsbBus and `max_packet_size` in bytes. For
+    /// full-speed devices, `max_packet_size` has to be one of 8, 16, 32 or 64.
+    pub fn new(builder: &mut Builder<'d, D>, state: &'d mut State<'d>, max_packet_size: u16) -> Self {
+        assert!(builder.control_buf_len() >= CAPABILITIES_LEN);
+        assert!(max_packet_size as usize <= MAX_PACKET_SIZE && max_packet_size as usize >= HEADER_LEN);
+
+        let mut func = builder.function(USB_CLASS_APPLICATION_SPECIFIC, USBTMC_SUBCLASS, USBTMC_PROTOCOL_USB488);
+
+        let mut iface = func.interface();
+        let iface_number = iface.interface_number();
+        let mut alt = iface.alt_setting(
+            USB_CLASS_APPLICATION_SPECIFIC,
+            USBTMC_SUBCLASS,
+            USBTMC_PROTOCOL_USB488,
+            None,
+        );
+        let read_ep = alt.endpoint_bulk_out(max_packet_size);
+        let write_ep = alt.endpoint_bulk_in(max_packet_size);
+
+        drop(func);
+
+        let control = state.control.write(Control {
+            iface: iface_number,
+            read_ep: read_ep.info().addr.into(),
+            write_ep: write_ep.info().addr.into(),
+            shared: &state.shared,
+        });
+        builder.handler(control);
+
+        UsbTmcClass {
+            read_ep,
+            write_ep,
+            control: &state.shared,
+        }
+    }
+
+    /// Sets the status byte reported to `READ_STATUS_BYTE`, e.g. for the SCPI status model.
+    pub fn set_status_byte(&self, status_byte: u8) {
+        self.control.status_byte.store(status_byte, Ordering::Relaxed);
+    }
+
+    /// Waits for the USB host to enable this interface
+    pub async fn wait_connection(&mut self) {
+        self.read_ep.wait_enabled().await;
+    }
+
+    // reads a packet unless the host clears the device or aborts the transfer in progress
+    async fn read_packet(&mut self, packet: &mut [u8]) -> Result<Option<usize>, EndpointError> {
+        match select(self.read_ep.read(packet), self.control.read_interrupted()).await {
+            Either::First(n) => n.map(Some),
+            Either::Second(()) => Ok(None),
+        }
+    }
+
+    /// Waits for the next message from the host. The data of a device dependent message is
+    /// written to `buf`, if it doesn't fit the whole message is dropped and
+    /// `EndpointError::BufferOverflow` is returned.
+    pub async fn read_message(&mut self, buf: &mut [u8]) -> Result<Message, EndpointError> {
+        let max_packet_size = self.read_ep.info().max_packet_size as usize;
+        let mut packet = [0; MAX_PACKET_SIZE];
+
+        'messages: loop {
+            self.control.read_tag.store(0, Ordering::Relaxed);
+            // an abort of a finished transfer has nothing left to do
+            ControlShared::take(&self.control.abort_read);
+            if ControlShared::take(&self.control.clear) {
+                self.control.write_tag.store(0, Ordering::Relaxed);
+                return Ok(Message::Clear);
+            }
+
+            let n = match self.read_packet(&mut packet).await? {
+                Some(n) => n,
+                None => continue,
+            };
+            let tag = packet[1];
+            if n < HEADER_LEN || tag == 0 || packet[2] != !tag {
+                warn!("USBTMC invalid header");
+                continue;
+            }
+            let size = u32::from_le_bytes([packet[4], packet[5], packet[6], packet[7]]);
+
+            match packet[0] {
+                MSG_DEV_DEP_MSG_OUT => {
+                    let size = size as usize;
+                    let eom = packet[8] & 0x01 != 0;
+                    self.control.read_tag.store(tag, Ordering::Relaxed);
+
+                    let mut data = HEADER_LEN..n;
+                    let mut received = 0;
+                    loop {
+                        // anything after the size is alignment padding
+                        let count = data.len().min(size - received);
+                        if received + count <= buf.len() {
+                            buf[received..received + count].copy_from_slice(&packet[data.start..data.start + count]);
+                        }
+                        received += count;
+                        self.control.read_count.store(received as u32, Ordering::Relaxed);
+
+                        // the transfer ends with its size or a short packet
+                        if received == size || data.end < max_packet_size {
+                            break;
+                        }
+                        match self.read_packet(&mut packet).await? {
+                            Some(n) => data = 0..n,
+                            None => continue 'messages,
+                        }
+                    }
+                    self.control.read_tag.store(0, Ordering::Relaxed);
+
+                    if received > buf.len() {
+                        return Err(EndpointError::BufferOverflow);
+                    }
+                    return Ok(Message::Data { len: received, eom });
+                }
+                MSG_REQUEST_DEV_DEP_MSG_IN => {
+                    return Ok(Message::ReadRequest(ReadRequest { tag, max_len: size }));
+                }
+                id => warn!("USBTMC unsupported message {}", id),
+            }
+        }
+    }
+
+    /// Answers a read request with `data`, at most the length the host asked for is sent.
+    /// Without `eom` the host sends another read request for the rest of the answer.
+    ///
+    /// Returns the number of bytes sent, less than requested if the host aborted the transfer.
+    pub async fn write_message(
+        &mut self,
+        request: ReadRequest,
+        data: &[u8],
+        eom: bool,
+    ) -> Result<usize, EndpointError> {
+        self.control.write_tag.store(request.tag, Ordering::Relaxed);
+        self.control.write_count.store(0, Ordering::Relaxed);
+        ControlShared::take(&self.control.abort_write);
+
+        let result = self.write_transfer(request, data, eom).await;
+
+        self.control.write_tag.store(0, Ordering::Relaxed);
+        ControlShared::take(&self.control.abort_write);
+        result
+    }
+
+    async fn write_transfer(&mut self, request: ReadRequest, data: &[u8], eom: bool) -> Result<usize, EndpointError> {
+        let max_packet_size = self.write_ep.info().max_packet_size as usize;
+        let len = data.len().min(request.max_len());
+        let eom = eom && len == data.len();
+
+        let mut header = [0; HEADER_LEN];
+        header[0] = MSG_DEV_DEP_MSG_IN;
+        header[1] = request.tag;
+        header[2] = !request.tag;
+        header[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+        header[8] = eom as u8;
+
+        // the data is padded to a multiple of 4 bytes
+        let total = HEADER_LEN + ((len + 3) & !3);
+        let mut packet = [0; MAX_PACKET_SIZE];
+        let mut offset = 0;
+        loop {
+            let count = (total - offset).min(max_packet_size);
+            for (i, byte) in packet[..count].iter_mut().enumerate() {
+                let pos = offset + i;
+                *byte = if pos < HEADER_LEN {
+                    header[pos]
+                } else if pos < HEADER_LEN + len {
+                    data[pos - HEADER_LEN]
+                } else {
+                    0
+                };
+            }
+            self.write_ep.write(&packet[..count]).await?;
+            offset += count;
+
+            let sent = offset.saturating_sub(HEADER_LEN).min(len);
+            self.control.write_count.store(sent as u32, Ordering::Relaxed);
+
+            let interrupted =
+                self.control.clear.load(Ordering::Relaxed) || self.control.abort_write.load(Ordering::Relaxed);
+            if offset == total || interrupted {
+                // a full packet doesn't end the transfer
+                if count == max_packet_size {
+                    self.write_ep.write(&[]).await?;
+                }
+                return Ok(sent);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, unwrap};
+use embassy_executor::Spawner;
+use embassy_stm32::flash::Flash;
+use {defmt_rtt as _, panic_probe as _};
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_stm32::init(Default::default());
+    info!("Hello EEPROM!");
+
+    const ADDR: u32 = 0x100;
+
+    let mut f = Flash::new_blocking(p.FLASH);
+
+    info!("Reading...");
+    let mut buf = [0u8; 8];
+    unwrap!(f.eeprom_read(ADDR, &mut buf));
+    info!("Read: {=[u8]:x}", buf);
+
+    info!("Erasing...");
+    unwrap!(f.eeprom_erase(ADDR, ADDR + 8));
+
+    info!("Reading...");
+    let mut buf = [0u8; 8];
+    unwrap!(f.eeprom_read(ADDR, &mut buf));
+    info!("Read after erase: {=[u8]:x}", buf);
+
+    info!("Writing...");
+    unwrap!(f.eeprom_write(ADDR, &[1, 2, 3, 4, 5, 6, 7, 8]));
+
+    info!("Reading...");
+    let mut buf = [0u8; 8];
+    unwrap!(f.eeprom_read(ADDR, &mut buf));
+    info!("Read: {=[u8]:x}", buf);
+    assert_eq!(&buf[..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+}
@@ -0,0 +1,202 @@
+//! Waveform capture of the input voltage and the total current.
+//!
+//! While armed, every `decimation`-th ADC scan is written to a ring buffer. Once the buffer
+//! holds the pre-trigger points, the capture triggers on a level crossing, a load step or a
+//! request from the host and fills the rest of the buffer. The trigger point is always
+//! stored, even between two decimated points.
+
+pub const MAX_POINTS: usize = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CaptureState {
+    Idle = 0,
+    // filling the pre-trigger points and waiting for the trigger
+    Armed = 1,
+    Triggered = 2,
+    Done = 3,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TriggerSource {
+    Voltage = 0,
+    Current = 1,
+    // new setpoints, list and sweep steps and the edges of the transient mode
+    LoadStep = 2,
+    // only the host triggers
+    Manual = 3,
+}
+
+impl TriggerSource {
+    pub fn from_i32(value: i32) -> Option<TriggerSource> {
+        match value {
+            0 => Some(TriggerSource::Voltage),
+            1 => Some(TriggerSource::Current),
+            2 => Some(TriggerSource::LoadStep),
+            3 => Some(TriggerSource::Manual),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Edge {
+    Rising = 0,
+    Falling = 1,
+}
+
+#[derive(Clone, Copy)]
+pub struct CaptureConfig {
+    pub source: TriggerSource,
+    pub edge: Edge,
+    /// trigger level in mV or mA
+    pub level: i32,
+    /// points before the trigger point
+    pub pre_trigger: usize,
+    /// scans per stored point
+    pub decimation: u32,
+}
+
+impl CaptureConfig {
+    pub fn is_valid(&self) -> bool {
+        self.pre_trigger < MAX_POINTS && self.decimation > 0
+    }
+}
+
+/// voltage in mV, current in mA
+#[derive(Clone, Copy, Default)]
+pub struct CapturePoint {
+    pub voltage: i32,
+    pub current: i32,
+}
+
+pub struct Capture {
+    config: CaptureConfig,
+    pub state: CaptureState,
+    points: [CapturePoint; MAX_POINTS],
+    // position of the next point in the ring buffer
+    pos: usize,
+    // valid points in the ring buffer
+    filled: usize,
+    // points still to be stored after the trigger
+    remaining: usize,
+    // first point of the finished capture
+    start: usize,
+    // scans since the last stored point
+    skipped: u32,
+    // value of the trigger source in the last scan
+    last: Option<i32>,
+    forced: bool,
+}
+
+impl Capture {
+    pub const fn default() -> Self {
+        Capture {
+            config: CaptureConfig {
+                source: TriggerSource::Manual,
+                edge: Edge::Rising,
+                level: 0,
+                pre_trigger: 0,
+                decimation: 1,
+            },
+            state: CaptureState::Idle,
+            points: [CapturePoint {
+                voltage: 0,
+                current: 0,
+            }; MAX_POINTS],
+            pos: 0,
+            filled: 0,
+            remaining: 0,
+            start: 0,
+            skipped: 0,
+            last: None,
+            forced: false,
+        }
+    }
+
+    pub fn config(&self) -> CaptureConfig {
+        self.config
+    }
+
+    pub fn arm(&mut self, config: CaptureConfig) {
+        *self = Capture {
+            config,
+            state: CaptureState::Armed,
+            ..Capture::default()
+        };
+    }
+
+    pub fn abort(&mut self) {
+        if self.state != CaptureState::Done {
+            self.state = CaptureState::Idle;
+        }
+    }
+
+    /// Triggers as soon as the pre-trigger points are stored.
+    pub fn force_trigger(&mut self) {
+        self.forced = true;
+    }
+
+    /// Adds one ADC scan, `load_step` is set if the setpoint changed since the last scan.
+    pub fn add(&mut self, point: CapturePoint, load_step: bool) {
+        if !matches!(self.state, CaptureState::Armed | CaptureState::Triggered) {
+            return;
+        }
+
+        let value = match self.config.source {
+            TriggerSource::Voltage => Some(point.voltage),
+            TriggerSource::Current => Some(point.current),
+            _ => None,
+        };
+        let crossed = match (self.last, value) {
+            (Some(last), Some(value)) => match self.config.edge {
+                Edge::Rising => last < self.config.level && value >= self.config.level,
+                Edge::Falling => last > self.config.level && value <= self.config.level,
+            },
+            _ => false,
+        };
+        self.last = value;
+
+        let event =
+            self.forced || crossed || (load_step && self.config.source == TriggerSource::LoadStep);
+        let fired =
+            self.state == CaptureState::Armed && self.filled >= self.config.pre_trigger && event;
+
+        self.skipped += 1;
+        if !fired && self.skipped < self.config.decimation {
+            return;
+        }
+        self.skipped = 0;
+
+        self.points[self.pos] = point;
+        let index = self.pos;
+        self.pos = (self.pos + 1) % MAX_POINTS;
+        self.filled = (self.filled + 1).min(MAX_POINTS);
+
+        if fired {
+            self.state = CaptureState::Triggered;
+            self.start = (index + MAX_POINTS - self.config.pre_trigger) % MAX_POINTS;
+            self.remaining = MAX_POINTS - self.config.pre_trigger - 1;
+        } else if self.state == CaptureState::Triggered {
+            self.remaining -= 1;
+        }
+        if self.state == CaptureState::Triggered && self.remaining == 0 {
+            self.state = CaptureState::Done;
+        }
+    }
+
+    /// Number of points of a finished capture, the trigger point is at `pre_trigger`.
+    pub fn count(&self) -> usize {
+        match self.state {
+            CaptureState::Done => MAX_POINTS,
+            _ => 0,
+        }
+    }
+
+    /// Point of a finished capture, oldest first.
+    pub fn point(&self, index: usize) -> Option<CapturePoint> {
+        if index >= self.count() {
+            return None;
+        }
+        Some(self.points[(self.start + index) % MAX_POINTS])
+    }
+}
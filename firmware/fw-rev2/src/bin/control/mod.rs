@@ -0,0 +1,169 @@
+//! Regulated load modes that recompute the channel setpoints on every ADC sample.
+
+use core::sync::atomic::{AtomicI32, Ordering};
+
+use crate::factory::DEFAULT_VREFINT;
+use crate::settings::NUM_CHANNELS;
+
+// internal reference voltage in µV, the factory calibrated value once it was read
+static VREFINT: AtomicI32 = AtomicI32::new(DEFAULT_VREFINT);
+
+/// Sets the internal reference voltage in µV that all samples are converted with.
+pub fn set_vrefint(vrefint: i32) {
+    VREFINT.store(vrefint, Ordering::Relaxed);
+}
+
+/// Internal reference voltage in µV
+pub fn vrefint() -> i32 {
+    VREFINT.load(Ordering::Relaxed)
+}
+
+// input voltage divider 33k/10k
+const V_DIV_NUM: i64 = 33_000 + 10_000;
+const V_DIV_DEN: i64 = 10_000;
+
+// 4mR shunt with a 31.6k/1k sense amplifier in µV per A
+const CURRENT_SENSE: i64 = 4 * (31_600 + 1_000);
+
+// upper bound for the time between two samples so the integrator
+// does not jump after a pause in sampling
+const MAX_DT_US: i64 = 10_000;
+
+// below this voltage in mV the constant-power mode sinks no current,
+// otherwise the setpoint runs away when the source collapses
+const CP_MIN_VOLTAGE: i32 = 100;
+
+/// One set of raw ADC samples
+#[derive(Clone, Copy, Default)]
+pub struct Sample {
+    pub ch: [i32; NUM_CHANNELS],
+    pub cal: i32,
+    pub v: i32,
+}
+
+impl Sample {
+    fn to_microvolts(&self, raw: i32) -> i64 {
+        if self.cal <= 0 {
+            return 0;
+        }
+        raw as i64 * vrefint() as i64 / self.cal as i64
+    }
+
+    /// Input voltage in mV
+    pub fn voltage(&self) -> i32 {
+        (self.to_microvolts(self.v) * V_DIV_NUM / V_DIV_DEN / 1000) as i32
+    }
+
+    /// Measured current of all channels in mA
+    pub fn total_current(&self) -> i32 {
+        let uv: i64 = self.ch.iter().map(|&ch| self.to_microvolts(ch)).sum();
+        (uv * 1000 / CURRENT_SENSE) as i32
+    }
+
+    /// Measured current of every channel in mA
+    pub fn currents(&self) -> [i32; NUM_CHANNELS] {
+        self.ch
+            .map(|ch| (self.to_microvolts(ch) * 1000 / CURRENT_SENSE) as i32)
+    }
+
+    // inverse of to_microvolts, scaled with the reference of this sample
+    fn from_microvolts(&self, uv: i64) -> u16 {
+        if self.cal <= 0 {
+            return 0;
+        }
+        (uv * self.cal as i64 / vrefint() as i64).clamp(0, u16::MAX as i64) as u16
+    }
+
+    /// Raw ADC code of a channel current in mA
+    pub fn current_to_raw(&self, current: i32) -> u16 {
+        self.from_microvolts(current as i64 * CURRENT_SENSE / 1000)
+    }
+
+    /// Raw ADC code of an input voltage in mV
+    pub fn voltage_to_raw(&self, voltage: i32) -> u16 {
+        self.from_microvolts(voltage as i64 * 1000 * V_DIV_DEN / V_DIV_NUM)
+    }
+}
+
+/// Constant-voltage regulation with a PI controller.
+///
+/// Sinking more current pulls the source voltage down, so the load current is
+/// increased as long as the measured voltage is above the target.
+pub struct CvController {
+    /// target voltage in mV
+    pub voltage: i32,
+    /// total current limit in mA
+    pub max_current: i32,
+    /// proportional gain in mA per V
+    pub kp: i32,
+    /// integral gain in mA per V·s
+    pub ki: i32,
+    // integrator state in µA
+    integral: i64,
+    // last current setpoint in mA
+    output: i32,
+}
+
+impl CvController {
+    pub const DEFAULT_KP: i32 = 2_000;
+    pub const DEFAULT_KI: i32 = 50_000;
+
+    pub fn new(voltage: i32, max_current: i32, kp: i32, ki: i32) -> Self {
+        CvController {
+            voltage,
+            max_current: max_current.max(0),
+            kp,
+            ki,
+            integral: 0,
+            output: 0,
+        }
+    }
+
+    /// Runs one controller step and returns the total current setpoint in mA.
+    pub fn update(&mut self, voltage: i32, dt_us: i64) -> i32 {
+        let error = (voltage - self.voltage) as i64;
+        let max_current = self.max_current as i64;
+        let p = self.kp as i64 * error / 1000;
+
+        // anti-windup: only integrate while the output is not saturated
+        // in the direction of the error
+        let output = p + self.integral / 1000;
+        let saturated = (output >= max_current && error > 0) || (output <= 0 && error < 0);
+        if !saturated {
+            let dt_us = dt_us.clamp(0, MAX_DT_US);
+            self.integral += self.ki as i64 * error * dt_us / 1_000_000;
+            self.integral = self.integral.clamp(0, max_current * 1000);
+        }
+
+        self.output = (p + self.integral / 1000).clamp(0, max_current) as i32;
+        self.output
+    }
+
+    /// Current setpoint of the last step in mA.
+    pub fn output(&self) -> i32 {
+        self.output
+    }
+}
+
+/// Constant-resistance mode, returns the total current in mA
+/// for a voltage in mV and a resistance in mΩ.
+pub fn cr_current(voltage: i32, resistance: i32) -> i32 {
+    if resistance <= 0 || voltage <= 0 {
+        return 0;
+    }
+    (voltage as i64 * 1000 / resistance as i64).min(i32::MAX as i64) as i32
+}
+
+/// Constant-power mode, returns the total current in mA
+/// for a voltage in mV and a power in mW.
+pub fn cp_current(voltage: i32, power: i32) -> i32 {
+    if power <= 0 || voltage < CP_MIN_VOLTAGE {
+        return 0;
+    }
+    (power as i64 * 1000 / voltage as i64) as i32
+}
+
+/// Splits a total current evenly over all channels.
+pub fn split_current(total: i32) -> [i32; NUM_CHANNELS] {
+    [total / NUM_CHANNELS as i32; NUM_CHANNELS]
+}
@@ -0,0 +1,138 @@
+//! Battery discharge test with cutoff voltage and charge/energy accumulation.
+//!
+//! The battery is discharged at constant current, resistance or power until the voltage
+//! stays below the cutoff for the debounce time. Charge and energy are integrated from
+//! the measured voltage and current of every ADC sample.
+
+use crate::list::{StepMode, Target};
+
+// mA·µs and mW·µs per mAh and mWh
+const PER_HOUR: i64 = 3_600_000_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DischargeState {
+    Idle = 0,
+    Running = 1,
+    // the cutoff voltage was reached
+    Done = 2,
+    Aborted = 3,
+}
+
+#[derive(Clone, Copy)]
+pub struct DischargeConfig {
+    /// current in mA, resistance in mΩ or power in mW
+    pub mode: StepMode,
+    pub setpoint: i32,
+    /// cutoff voltage in mV
+    pub cutoff: i32,
+    /// time the voltage has to stay below the cutoff in ms
+    pub debounce: i32,
+}
+
+impl DischargeConfig {
+    pub fn is_valid(&self) -> bool {
+        self.mode != StepMode::Voltage && self.setpoint > 0 && self.cutoff > 0 && self.debounce >= 0
+    }
+}
+
+pub struct DischargeStatus {
+    pub state: DischargeState,
+    /// time since the start in µs
+    pub elapsed: u64,
+    // charge in mA·µs
+    charge: i64,
+    // energy in mW·µs
+    energy: i64,
+}
+
+impl DischargeStatus {
+    pub const fn default() -> Self {
+        DischargeStatus {
+            state: DischargeState::Idle,
+            elapsed: 0,
+            charge: 0,
+            energy: 0,
+        }
+    }
+
+    pub fn running() -> Self {
+        DischargeStatus {
+            state: DischargeState::Running,
+            ..Self::default()
+        }
+    }
+
+    /// Discharged charge in mAh
+    pub fn charge_mah(&self) -> i32 {
+        (self.charge / PER_HOUR) as i32
+    }
+
+    /// Discharged energy in mWh
+    pub fn energy_mwh(&self) -> i32 {
+        (self.energy / PER_HOUR) as i32
+    }
+}
+
+pub struct Discharge {
+    config: DischargeConfig,
+    start: u64,
+    started: bool,
+    last_sample: Option<u64>,
+    // time the voltage fell below the cutoff
+    below_since: Option<u64>,
+}
+
+impl Discharge {
+    pub fn new(config: DischargeConfig, now: u64) -> Self {
+        Discharge {
+            config,
+            start: now,
+            started: false,
+            last_sample: None,
+            below_since: None,
+        }
+    }
+
+    /// Integrates a sample with the voltage in mV and the total current in mA taken at
+    /// `now`, returns `true` when the cutoff voltage was reached.
+    pub fn add_sample(
+        &mut self,
+        now: u64,
+        voltage: i32,
+        current: i32,
+        status: &mut DischargeStatus,
+    ) -> bool {
+        if let Some(last) = self.last_sample {
+            let dt = (now - last) as i64;
+            status.charge += current.max(0) as i64 * dt;
+            status.energy += (voltage.max(0) as i64 * current.max(0) as i64 / 1000) * dt;
+        }
+        self.last_sample = Some(now);
+        status.elapsed = now - self.start;
+
+        if voltage >= self.config.cutoff {
+            self.below_since = None;
+            return false;
+        }
+
+        let below_since = *self.below_since.get_or_insert(now);
+        if now - below_since >= self.config.debounce as u64 * 1000 {
+            status.state = DischargeState::Done;
+            return true;
+        }
+        false
+    }
+
+    /// The setpoint stays the same for the whole discharge.
+    pub fn poll(&mut self) -> Target {
+        let new_step = !self.started;
+        self.started = true;
+
+        Target {
+            mode: self.config.mode,
+            setpoint: self.config.setpoint,
+            new_step,
+            deadline: None,
+        }
+    }
+}
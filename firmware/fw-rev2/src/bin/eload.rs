@@ -1,42 +1,115 @@
 #![no_std]
 #![no_main]
 
+use core::cell::{Cell, RefCell};
+use core::fmt::Write as _;
+use core::future::pending;
 use core::option::Option::Some;
 use defmt::{panic, *};
 use defmt_rtt as _; // global logger
 use embassy_executor::Spawner;
 use embassy_stm32::adc::*;
 use embassy_stm32::dma::NoDma;
+use embassy_stm32::flash::{Blocking, Flash};
 use embassy_stm32::gpio::{Level, Output, OutputType, Speed};
 use embassy_stm32::i2c;
 use embassy_stm32::i2c::I2c;
-use embassy_stm32::spi::Spi;
+use embassy_stm32::interrupt;
+use embassy_stm32::interrupt::InterruptExt;
+use embassy_stm32::pac::adc::vals::Exten;
+use embassy_stm32::pac::timer::vals::Mms;
+use embassy_stm32::rcc::low_level::RccPeripheral;
 use embassy_stm32::rcc::*;
+use embassy_stm32::spi::Spi;
 use embassy_stm32::time::{khz, Hertz};
+use embassy_stm32::timer::low_level::Basic16bitInstance;
 use embassy_stm32::timer::simple_pwm::{PwmPin, SimplePwm};
 use embassy_stm32::timer::Channel as PWMChannel;
 use embassy_stm32::usb::{Driver, Instance};
+use embassy_stm32::wdg::IndependentWatchdog;
 use embassy_stm32::{adc, bind_interrupts, peripherals, usb, Config};
-use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, ThreadModeRawMutex};
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
 use embassy_sync::channel::Channel;
 use embassy_sync::mutex::Mutex;
-use embassy_time::{Delay, Timer};
+use embassy_sync::signal::Signal;
+use embassy_time::{Delay, Duration, Instant, Timer};
 
 use embassy_stm32::timer::OutputPolarity;
 use embassy_usb::class::cdc_acm::{CdcAcmClass, State};
+use embassy_usb::class::usbtmc::{Message, State as TmcState, UsbTmcClass};
 use embassy_usb::driver::EndpointError;
 use embassy_usb::Builder;
-use futures::future::join3;
-use panic_probe as _;
+use futures::future::{join, join4, select, Either};
+use futures::pin_mut;
 
 extern crate alloc;
 extern crate alloc_cortex_m;
 
 mod protobuf;
-use protobuf::coms::{QControl, QRequest, QResponse, QState};
-use quick_protobuf::{self, MessageWrite};
+use protobuf::coms::{
+    QAwd, QCalibration, QCapture, QCaptureData, QCaptureStatus, QCommTimeout, QControl, QCp, QCr,
+    QCv, QDefaults, QDischarge, QDischargeStatus, QEvent, QFanCurve, QInfo, QIvCurve, QLimits,
+    QListRun, QListStep, QMcuCalibration, QMppt, QMpptStatus, QOcp, QOcpResult, QRawState,
+    QRequest, QResponse, QSoa, QSoaStatus, QState, QStatistics, QStatisticsWindow, QSubscribe,
+    QSweep, QSweepPoint, QSweepStatus, QThermal, QTransient,
+};
+
+mod settings;
+use settings::Settings;
+
+mod control;
+use control::{CvController, Sample};
+
+mod transient;
+use transient::{Transient, TransientConfig, Trigger};
+
+mod list;
+use list::{ListRunner, ListState, Step, StepMode, MAX_STEPS};
+
+mod sweep;
+use sweep::{Sweep, SweepConfig, SweepResults, SweepState};
+
+mod ocp;
+use ocp::{OcpConfig, OcpResult, OcpState, OcpTest};
+
+mod discharge;
+use discharge::{Discharge, DischargeConfig, DischargeState, DischargeStatus};
+
+mod mppt;
+use mppt::{IvCurve, IvCurveConfig, Mppt, MpptConfig, MpptState, MpptStatus};
+
+mod thermal;
+use thermal::ThermalState;
+
+mod soa;
+use soa::{SoaMonitor, SoaState, SoaStatus};
+
+mod watchdog;
+use watchdog::ResetCause;
+
+mod stats;
+use stats::Statistics;
+
+mod capture;
+use capture::{Capture, CaptureConfig, CapturePoint, CaptureState, Edge, TriggerSource};
+
+mod factory;
+use factory::FactoryCalibration;
+
+mod framing;
+use framing::Decoder;
+
+mod events;
+use events::{Event, EventDetector, Snapshot};
+
+mod scpi;
+use quick_protobuf::sizeofs::sizeof_len;
+use quick_protobuf::{self, BytesReader, BytesWriter, MessageRead, MessageWrite, Writer};
+use scpi::{Command, ErrorQueue, Function, Milli, Quantity, ScpiError};
 
 use alloc::borrow::Cow;
+use alloc::vec::Vec;
 
 use alloc_cortex_m::CortexMHeap;
 
@@ -45,18 +118,87 @@ static ALLOCATOR: CortexMHeap = CortexMHeap::empty();
 
 bind_interrupts!(struct Irqs {
     USB => usb::InterruptHandler<peripherals::USB>;
-    ADC1_COMP => adc::InterruptHandler<ADC>;
+    // the watchdog handler has to see the flags before the driver disables the interrupt
+    ADC1_COMP => AnalogWatchdogHandler, adc::InterruptHandler<ADC>;
     I2C1 => i2c::EventInterruptHandler<peripherals::I2C1>, i2c::ErrorInterruptHandler<peripherals::I2C1>;
 });
 use embassy_stm32::peripherals::*;
 
+enum Mode {
+    // raw DAC codes (e.g. for calibration)
+    Raw([i32; 4]),
+    // per-channel current setpoints in mA
+    Current([i32; 4]),
+    Voltage(CvController),
+    // resistance in mΩ
+    Resistance(i32),
+    // power in mW
+    Power(i32),
+    // DACs are updated from the TIM6 interrupt
+    Transient(TransientConfig),
+}
+
+impl Mode {
+    fn id(&self) -> i32 {
+        match self {
+            Mode::Raw(_) => 0,
+            Mode::Current(_) => 1,
+            Mode::Voltage(_) => 2,
+            Mode::Resistance(_) => 3,
+            Mode::Power(_) => 4,
+            Mode::Transient(_) => 5,
+        }
+    }
+
+    // regulated modes recompute the setpoints on every ADC sample
+    fn is_regulated(&self) -> bool {
+        matches!(
+            self,
+            Mode::Voltage(_) | Mode::Resistance(_) | Mode::Power(_)
+        )
+    }
+}
+
 struct LoadControl {
-    sdn: i32,
-    pwm: i32,
-    dac0: i32,
-    dac1: i32,
-    dac2: i32,
-    dac3: i32,
+    // `None` leaves the shutdown state unchanged
+    sdn: Option<i32>,
+    mode: Mode,
+}
+
+enum SequenceCommand {
+    ListRun { count: usize, loops: i32 },
+    Sweep(SweepConfig),
+    Ocp(OcpConfig),
+    Discharge(DischargeConfig),
+    Mppt(MpptConfig),
+    IvCurve(IvCurveConfig),
+    Pause,
+    Resume,
+    Abort,
+}
+
+// sequences that drive the setpoints of the regular modes over time
+enum Sequence {
+    List(ListRunner),
+    Sweep(Sweep),
+    Ocp(OcpTest),
+    Discharge(Discharge),
+    Mppt(Mppt),
+    IvCurve(IvCurve),
+}
+
+impl Sequence {
+    // the sweep, MPPT and I-V curve average the measurements of every step, the OCP
+    // test checks every sample for a trip and the discharge test integrates them
+    fn needs_samples(&self) -> bool {
+        !matches!(self, Sequence::List(_))
+    }
+
+    // lists and discharge tests run for hours without the host, the other sequences are
+    // measurements that the host reads back right away
+    fn unattended(&self) -> bool {
+        matches!(self, Sequence::List(_) | Sequence::Discharge(_))
+    }
 }
 
 struct LoadState {
@@ -66,12 +208,394 @@ struct LoadState {
     ch3: i32,
     cal: i32,
     v: i32,
+    // raw reading of the MCU temperature sensor
+    ts: i32,
     temp: i32,
     sdn: i32,
+    mode: i32,
+    list_state: i32,
+    list_step: i32,
+    list_loop: i32,
+    thermal: i32,
+    // latched faults, FAULT_* bits
+    fault: i32,
+    // a list or discharge test runs, the host watchdog leaves it running
+    unattended: bool,
 }
 
+// the load was shut down above the trip temperature
+const FAULT_OVER_TEMP: i32 = 1 << 0;
+// the board temperature sensor didn't answer TEMP_SENSOR_RETRIES times in a row
+const FAULT_TEMP_SENSOR: i32 = 1 << 1;
+// the load was shut down by the SOA limiter
+const FAULT_SOA: i32 = 1 << 2;
+// no request from the host within the communication timeout
+const FAULT_HOST_TIMEOUT: i32 = 1 << 3;
+// USB was suspended or unplugged while the load was on
+const FAULT_HOST_LOST: i32 = 1 << 4;
+// a current or the input voltage left the window of the ADC analog watchdog
+const FAULT_AWD: i32 = 1 << 5;
+
+// failed readings of the temperature sensor until the load is shut down, and the time between
+// the retries
+const TEMP_SENSOR_RETRIES: u32 = 4;
+const TEMP_SENSOR_RETRY_MS: u64 = 250;
+
+// kinds of the messages from the device
+const MSG_RESPONSE: i32 = 0;
+const MSG_STATE: i32 = 1;
+const MSG_EVENT: i32 = 2;
+
+// incremented on incompatible changes of the protocol, 2 introduced the framing
+const PROTOCOL_VERSION: i32 = 2;
+const HARDWARE_REVISION: i32 = 2;
+
+// modes and features of this firmware, reported in the info
+const CAP_CONSTANT_CURRENT: i32 = 1 << 0;
+const CAP_CONSTANT_VOLTAGE: i32 = 1 << 1;
+const CAP_CONSTANT_RESISTANCE: i32 = 1 << 2;
+const CAP_CONSTANT_POWER: i32 = 1 << 3;
+const CAP_TRANSIENT: i32 = 1 << 4;
+const CAP_LIST: i32 = 1 << 5;
+const CAP_SWEEP: i32 = 1 << 6;
+const CAP_OCP: i32 = 1 << 7;
+const CAP_DISCHARGE: i32 = 1 << 8;
+const CAP_MPPT: i32 = 1 << 9;
+const CAP_CAPTURE: i32 = 1 << 10;
+const CAP_STATISTICS: i32 = 1 << 11;
+const CAP_AWD: i32 = 1 << 12;
+const CAP_SCPI: i32 = 1 << 13;
+const CAP_USBTMC: i32 = 1 << 14;
+const CAPABILITIES: i32 = CAP_CONSTANT_CURRENT
+    | CAP_CONSTANT_VOLTAGE
+    | CAP_CONSTANT_RESISTANCE
+    | CAP_CONSTANT_POWER
+    | CAP_TRANSIENT
+    | CAP_LIST
+    | CAP_SWEEP
+    | CAP_OCP
+    | CAP_DISCHARGE
+    | CAP_MPPT
+    | CAP_CAPTURE
+    | CAP_STATISTICS
+    | CAP_AWD
+    | CAP_SCPI
+    | CAP_USBTMC;
+
+const MANUFACTURER: &str = "microengineer";
+const PRODUCT: &str = "E-Load";
+
+// longest SCPI command line and the longest answer of one query
+const SCPI_LINE_LEN: usize = 128;
+const SCPI_REPLY_LEN: usize = 96;
+
+// USB488 status byte bits: error available and message available
+const STB_EAV: u8 = 1 << 2;
+const STB_MAV: u8 = 1 << 4;
+
 static LOAD_CONTROL: Channel<ThreadModeRawMutex, LoadControl, 1> = Channel::new();
 
+static SEQUENCE_CONTROL: Channel<ThreadModeRawMutex, SequenceCommand, 1> = Channel::new();
+
+// steps of the list mode, uploaded one by one by the host
+static LIST_STEPS: Mutex<ThreadModeRawMutex, [Step; MAX_STEPS]> =
+    Mutex::new([Step::default(); MAX_STEPS]);
+
+// points of the last sweep, downloaded by the host when the sweep is done
+static SWEEP_RESULTS: Mutex<ThreadModeRawMutex, SweepResults> = Mutex::new(SweepResults::default());
+
+static OCP_RESULT: Mutex<ThreadModeRawMutex, OcpResult> = Mutex::new(OcpResult::default());
+
+// kept on the device, so a discharge runs on without the host
+static DISCHARGE_STATUS: Mutex<ThreadModeRawMutex, DischargeStatus> =
+    Mutex::new(DischargeStatus::default());
+
+// operating point of the maximum power point tracker, the I-V curve goes to SWEEP_RESULTS
+static MPPT_STATUS: Mutex<ThreadModeRawMutex, MpptStatus> = Mutex::new(MpptStatus::default());
+
+// latest ADC samples for the regulated modes
+static SAMPLES: Signal<ThreadModeRawMutex, Sample> = Signal::new();
+
+// average, min, max and RMS of every scan since the host reset them
+static STATISTICS: Mutex<ThreadModeRawMutex, Statistics> = Mutex::new(Statistics::default());
+
+// state pushes and events requested by the host, reset when it disconnects
+#[derive(Clone, Copy)]
+struct Subscription {
+    // ms between two state pushes, 0 disables them
+    interval: u32,
+    events: bool,
+}
+
+static SUBSCRIPTION: Mutex<ThreadModeRawMutex, Subscription> = Mutex::new(Subscription {
+    interval: 0,
+    events: false,
+});
+
+// events for the host, new ones are dropped if it doesn't keep up
+static EVENTS: Channel<ThreadModeRawMutex, Event, 8> = Channel::new();
+
+// waveform capture of the voltage and the total current, downloaded in chunks
+static CAPTURE: Mutex<ThreadModeRawMutex, Capture> = Mutex::new(Capture::default());
+
+// time of the last setpoint change in µs, set from the transient interrupt as well
+static LOAD_STEP: BlockingMutex<CriticalSectionRawMutex, Cell<Option<u64>>> =
+    BlockingMutex::new(Cell::new(None));
+
+// index in the scan and raw value that tripped the analog watchdog, set by its interrupt handler
+static AWD_TRIP: BlockingMutex<CriticalSectionRawMutex, Cell<Option<(usize, u16)>>> =
+    BlockingMutex::new(Cell::new(None));
+
+// limits checked by the analog watchdog interrupt, set by the ADC task for every block
+static AWD_LIMITS: BlockingMutex<CriticalSectionRawMutex, Cell<AwdLimits>> =
+    BlockingMutex::new(Cell::new(AwdLimits::off()));
+
+#[derive(Clone, Copy)]
+struct AwdLimits {
+    // channel current limit in the 12 bits the watchdog compares
+    current: u16,
+    // raw input voltage window
+    voltage: Option<(u16, u16)>,
+}
+
+impl AwdLimits {
+    const fn off() -> Self {
+        AwdLimits {
+            current: 0xfff,
+            voltage: None,
+        }
+    }
+}
+
+fn load_step() {
+    let now = Instant::now().as_micros();
+    LOAD_STEP.lock(|step| step.set(Some(now)));
+}
+
+// channel and SOA point of the last violation
+static SOA_STATUS: Mutex<ThreadModeRawMutex, SoaStatus> = Mutex::new(SoaStatus::default());
+
+// scale of all channel setpoints in per mille, reduced by the over-temperature protection
+static DERATING: Signal<ThreadModeRawMutex, i32> = Signal::new();
+
+// minimum fan duty in percent requested by the host, the fan curve may drive it higher
+static FAN_PWM: Signal<ThreadModeRawMutex, i32> = Signal::new();
+
+// every valid request from the host resets the communication timeout
+static HEARTBEAT: Signal<ThreadModeRawMutex, ()> = Signal::new();
+
+// the USB host went away
+static HOST_LOST: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+static SETTINGS: Mutex<ThreadModeRawMutex, Settings> = Mutex::new(Settings::default());
+
+static EEPROM: Mutex<ThreadModeRawMutex, Option<Flash<'static, Blocking>>> = Mutex::new(None);
+
+struct Dacs {
+    spi: Spi<'static, SPI1, NoDma, NoDma>,
+    cs: [Output<'static>; 4],
+}
+
+impl Dacs {
+    fn write(&mut self, dac_val: &[i32; 4]) {
+        let mut buf = [0u8; 3];
+        for i in 0..4 {
+            self.cs[i].set_low();
+            let data = (dac_val[i] & 0xffff) << 6;
+
+            buf[0] = ((data & 0x00ff0000) >> 16) as u8;
+            buf[1] = ((data & 0x0000ff00) >> 8) as u8;
+            buf[2] = (data & 0x000000ff) as u8;
+
+            let _ = self.spi.blocking_write(&buf);
+
+            self.cs[i].set_high();
+        }
+    }
+}
+
+// shared between the control task and the transient timer interrupt
+static DACS: BlockingMutex<CriticalSectionRawMutex, RefCell<Option<Dacs>>> =
+    BlockingMutex::new(RefCell::new(None));
+
+// the device resets if the watchdog isn't fed for this long
+const WATCHDOG_TIMEOUT_US: u32 = 1_000_000;
+const WATCHDOG_FEED_MS: u64 = 250;
+
+// the ADC converts PA0-PA3 (channels 0-3), PB1 (9), VREFINT (17) and the temperature
+// sensor (18) on every TIM3 update, 8x oversampled to 15 bits. The order is the one of the
+// CHSELR bits, the indices are checked against the channel numbers of the driver at startup.
+const SCAN_HZ: u32 = 1000;
+const SCAN_LEN: usize = 7;
+const SCAN_V: usize = 4;
+const SCAN_CAL: usize = 5;
+const SCAN_TS: usize = 6;
+// largest code of the oversampled results
+const SCAN_FULL_SCALE: i32 = 8 * 4095;
+// scans per block, the samples of a block are averaged, the DMA buffer holds two blocks
+const SCAN_BLOCK: usize = 4;
+const SCAN_BUF_LEN: usize = 2 * SCAN_BLOCK * SCAN_LEN;
+// time between two averaged samples in ms, the shortest SOA pulse duration that is resolved
+const SAMPLE_MS: i32 = (SCAN_BLOCK as u32 * 1000 / SCAN_HZ) as i32;
+// EXTSEL of TIM3_TRGO
+const ADC_TRIGGER_TIM3_TRGO: u8 = 6;
+// the analog watchdog compares bits 15:4 of the oversampled result
+const AWD_SHIFT: u16 = 4;
+// largest data of a response, the rest of a message is left for the id, the error and
+// the length of the data
+const RESPONSE_DATA_LEN: usize = framing::MAX_MESSAGE - 16;
+
+// capture points per response, 8 bytes each
+const CAPTURE_CHUNK: usize = 28;
+
+// shortest and longest interval of the state pushes in ms
+const MIN_PUSH_INTERVAL: i32 = 10;
+const MAX_PUSH_INTERVAL: i32 = 60_000;
+
+// largest expected difference between the board and the MCU temperature in m°C
+const MCU_TEMP_TOLERANCE: i32 = 20_000;
+
+// the control task wakes up at least this often to check in with the watchdog
+const ALIVE_INTERVAL_MS: u64 = 250;
+
+static RESET_CAUSE: Mutex<ThreadModeRawMutex, ResetCause> = Mutex::new(ResetCause::Unknown);
+
+fn now_ms() -> u32 {
+    Instant::now().as_millis() as u32
+}
+
+// drives SDN (PB4) high directly, bypassing the output owned by the control task
+fn force_shutdown() {
+    embassy_stm32::pac::GPIOB
+        .bsrr()
+        .write(|w| w.set_bs(4, true));
+}
+
+// shuts the load down without relying on any task, used when the firmware failed
+fn fail_safe() {
+    force_shutdown();
+    DACS.lock(|dacs| {
+        // the DACs may have been borrowed when the firmware failed
+        if let Ok(mut dacs) = dacs.try_borrow_mut() {
+            if let Some(dacs) = dacs.as_mut() {
+                dacs.write(&[0; 4]);
+            }
+        }
+    });
+}
+
+#[panic_handler]
+fn fail_safe_panic(info: &core::panic::PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    // the load goes off before anything else, logging takes a while
+    fail_safe();
+    error!("panic: {}", Display2Format(info));
+    cortex_m::peripheral::SCB::sys_reset();
+}
+
+// defmt::panic! logs the message and continues in the regular panic handler
+#[defmt::panic_handler]
+fn defmt_panic() -> ! {
+    core::panic!("defmt panic");
+}
+
+// shuts the load down in the ADC interrupt right after the conversion of a channel current
+// above the limit or of an input voltage outside its window, the fault is latched by the ADC
+// task afterwards.
+//
+// The only watchdog window applies to every channel of the scan, so VREFINT, the temperature
+// sensor or the input voltage can leave it as well. The handler compares the latest result of
+// every channel against its own limits and only clears the flag if they are fine, the watchdog
+// stays armed. While a voltage window is set the hardware window is lowered below VREFINT, so
+// the handler runs at least once per scan, right after the input voltage was converted.
+struct AnalogWatchdogHandler;
+
+impl interrupt::typelevel::Handler<interrupt::typelevel::ADC1_COMP> for AnalogWatchdogHandler {
+    unsafe fn on_interrupt() {
+        let adc = embassy_stm32::pac::ADC;
+        if !(adc.isr().read().awd() && adc.ier().read().awdie()) {
+            return;
+        }
+
+        // the DMA moves every result right after its conversion, before the interrupt is
+        // entered, so the ring buffer holds the latest result of every channel
+        let dma = embassy_stm32::pac::DMA1.ch(0);
+        let buf = dma.mar().read() as *const u16;
+        let written = SCAN_BUF_LEN - dma.ndtr().read().ndt() as usize;
+        let limits = AWD_LIMITS.lock(|limits| limits.get());
+        for i in 1..=SCAN_LEN {
+            let index = (written + SCAN_BUF_LEN - i) % SCAN_BUF_LEN;
+            let channel = index % SCAN_LEN;
+            let value = core::ptr::read_volatile(buf.add(index));
+            let outside = match (channel, limits.voltage) {
+                (channel, _) if channel < settings::NUM_CHANNELS => {
+                    value >> AWD_SHIFT > limits.current
+                }
+                (SCAN_V, Some((low, high))) => !(low..=high).contains(&value),
+                _ => false,
+            };
+            if outside {
+                force_shutdown();
+                AWD_TRIP.lock(|trip| trip.set(Some((channel, value))));
+                // the driver disables the interrupt until the window is set again
+                return;
+            }
+        }
+        adc.isr().write(|w| w.set_awd(true));
+    }
+}
+
+// reads and clears the reset flags, they survive resets until they are cleared
+fn read_reset_cause() -> ResetCause {
+    let rcc = embassy_stm32::pac::RCC;
+    let csr = rcc.csr().read();
+    // the reset pin is driven by every internal reset, so it's checked last
+    let cause = if csr.iwdgrstf() {
+        ResetCause::IndependentWatchdog
+    } else if csr.wwdgrstf() {
+        ResetCause::WindowWatchdog
+    } else if csr.sftrstf() {
+        ResetCause::Software
+    } else if csr.lpwrrstf() {
+        ResetCause::LowPower
+    } else if csr.porrstf() {
+        ResetCause::PowerOn
+    } else if csr.pinrstf() {
+        ResetCause::Pin
+    } else {
+        ResetCause::Unknown
+    };
+    rcc.csr().modify(|w| w.set_rmvf(true));
+    cause
+}
+
+#[embassy_executor::task]
+async fn watchdog_task(mut wdg: IndependentWatchdog<'static, IWDG>) {
+    wdg.unleash();
+    loop {
+        match watchdog::stalled(now_ms()) {
+            None => wdg.pet(),
+            Some(task) => {
+                // the watchdog resets the device shortly
+                fail_safe();
+                error!("task {} stalled", task);
+            }
+        }
+        Timer::after_millis(WATCHDOG_FEED_MS).await;
+    }
+}
+
+struct TransientState {
+    config: TransientConfig,
+    generator: Transient,
+    // copy of the settings, the interrupt can't wait for the settings mutex
+    settings: Settings,
+    derating: i32,
+}
+
+static TRANSIENT: BlockingMutex<CriticalSectionRawMutex, RefCell<Option<TransientState>>> =
+    BlockingMutex::new(RefCell::new(None));
+
 static LOAD_STATE: Mutex<ThreadModeRawMutex, LoadState> = Mutex::new(LoadState {
     ch0: 0,
     ch1: 0,
@@ -79,8 +603,16 @@ static LOAD_STATE: Mutex<ThreadModeRawMutex, LoadState> = Mutex::new(LoadState {
     ch3: 0,
     cal: 0,
     v: 0,
+    ts: 0,
     temp: 0,
     sdn: 0,
+    mode: 0,
+    list_state: ListState::Idle as i32,
+    list_step: 0,
+    list_loop: 0,
+    thermal: ThermalState::Normal as i32,
+    fault: 0,
+    unattended: false,
 });
 
 #[embassy_executor::main]
@@ -108,14 +640,37 @@ async fn main(spawner: Spawner) {
 
     let mut p = embassy_stm32::init(config);
 
+    let reset_cause = read_reset_cause();
+    info!("reset cause: {}", reset_cause as i32);
+    *RESET_CAUSE.lock().await = reset_cause;
+
+    let mut flash = Flash::new_blocking(p.FLASH);
+    let settings = match settings::load(&mut flash) {
+        Some(settings) => settings,
+        None => {
+            warn!("using factory settings");
+            Settings::default()
+        }
+    };
+    *SETTINGS.lock().await = settings;
+    *EEPROM.lock().await = Some(flash);
+
+    // apply power-on defaults, picked up by the tasks once they are running
+    let _ = LOAD_CONTROL.try_send(LoadControl {
+        sdn: Some(settings.defaults.sdn),
+        mode: Mode::Current(settings.defaults.current),
+    });
+    FAN_PWM.signal(settings.defaults.pwm);
+
     let driver = Driver::new(p.USB, Irqs, p.PA12, p.PA11);
 
     // Create embassy-usb Config
     let mut config = embassy_usb::Config::new(0xc0de, 0xcafe);
     config.max_packet_size_0 = 64;
-    config.manufacturer = Some("microengineer");
-    config.product = Some("E-Load");
-    config.serial_number = Some("rev1");
+    config.manufacturer = Some(MANUFACTURER);
+    config.product = Some(PRODUCT);
+    // the unique ID of the MCU tells several loads on one host apart
+    config.serial_number = Some(embassy_stm32::uid::uid_hex());
 
     // Required for windows compatibility.
     // https://developer.nordicsemi.com/nRF_Connect_SDK/doc/1.9.1/kconfig/CONFIG_CDC_ACM_IAD.html#help
@@ -132,6 +687,9 @@ async fn main(spawner: Spawner) {
     let mut control_buf = [0; 64];
 
     let mut state_usb_ctrl = State::new();
+    let mut state_usb_scpi = State::new();
+    let mut state_usb_tmc = TmcState::new();
+    let mut usb_link_handler = UsbLinkHandler { configured: false };
 
     let mut builder = Builder::new(
         driver,
@@ -143,7 +701,13 @@ async fn main(spawner: Spawner) {
         &mut control_buf,
     );
 
+    builder.handler(&mut usb_link_handler);
+
     let mut class_usb_ctrl = CdcAcmClass::new(&mut builder, &mut state_usb_ctrl, 64);
+    // second port for SCPI text commands
+    let mut class_usb_scpi = CdcAcmClass::new(&mut builder, &mut state_usb_scpi, 64);
+    // the same SCPI commands for test & measurement software that expects an instrument
+    let mut class_usb_tmc = UsbTmcClass::new(&mut builder, &mut state_usb_tmc, 64);
 
     // Build the builder.
     let mut usb = builder.build();
@@ -178,53 +742,198 @@ async fn main(spawner: Spawner) {
 
     let mut spi_config = embassy_stm32::spi::Config::default();
     spi_config.mode = embassy_stm32::spi::MODE_2;
-    spi_config.frequency = Hertz(100_000);
+    // the DAC8411 is specified up to 50MHz, 4MHz keeps an update of all
+    // four DACs well within one transient tick
+    spi_config.frequency = Hertz(4_000_000);
 
-    let dac_spi = Spi::new(p.SPI1, p.PA5, p.PA7, p.PA6, p.DMA1_CH3, p.DMA1_CH2, spi_config);
+    let dac_spi = Spi::new(p.SPI1, p.PA5, p.PA7, p.PA6, NoDma, NoDma, spi_config);
 
     let dac_cs0 = Output::new(p.PA4, Level::High, Speed::VeryHigh);
     let dac_cs1 = Output::new(p.PA9, Level::High, Speed::VeryHigh);
     let dac_cs2 = Output::new(p.PA10, Level::High, Speed::VeryHigh);
     let dac_cs3 = Output::new(p.PA15, Level::High, Speed::VeryHigh);
 
+    DACS.lock(|dacs| {
+        dacs.replace(Some(Dacs {
+            spi: dac_spi,
+            cs: [dac_cs0, dac_cs1, dac_cs2, dac_cs3],
+        }))
+    });
+
     let led1 = Output::new(p.PB0, Level::High, Speed::Low);
     //let led2 = Output::new(p.PB1, Level::High, Speed::Low);
 
     let eload_sdn = Output::new(p.PB4, Level::High, Speed::Low);
 
     let mut adc = Adc::new(p.ADC, Irqs, &mut Delay);
+    // VREFINT and the temperature sensor need a sampling time of at least 10µs
     adc.set_sample_time(SampleTime::Cycles160_5);
+    adc.set_oversampling(OversamplingRatio::X8, 0);
 
     let mut vrefint = adc.enable_vref(&mut Delay);
+    let mut temperature = adc.enable_temperature(&mut Delay);
+
+    let factory = FactoryCalibration::read();
+    if factory.is_valid() {
+        control::set_vrefint(factory.vrefint());
+    } else {
+        warn!("no factory calibration of VREFINT, using the nominal value");
+    }
+    info!(
+        "factory calibration - vrefint_cal: {}, ts_cal1: {}, ts_cal2: {}",
+        factory.vrefint_cal, factory.ts_cal1, factory.ts_cal2
+    );
+
+    let mut sequence = adc::Sequence::new();
+    sequence
+        .add(&mut p.PA0)
+        .add(&mut p.PA1)
+        .add(&mut p.PA2)
+        .add(&mut p.PA3)
+        .add(&mut p.PB1)
+        .add(&mut temperature)
+        .add(&mut vrefint);
+    assert_eq!(sequence.len(), SCAN_LEN);
+    assert_eq!(sequence.index(&p.PA0), Some(0));
+    assert_eq!(sequence.index(&p.PA3), Some(settings::NUM_CHANNELS - 1));
+    assert_eq!(sequence.index(&p.PB1), Some(SCAN_V));
+    assert_eq!(sequence.index(&vrefint), Some(SCAN_CAL));
+    assert_eq!(sequence.index(&temperature), Some(SCAN_TS));
+
+    // there is only one watchdog window, the interrupt handler sorts out the channels
+    adc.set_watchdog(WatchdogChannels::All, 0, 0xfff);
+
+    let trigger = adc::Trigger {
+        extsel: ADC_TRIGGER_TIM3_TRGO,
+        edge: Exten::RISINGEDGE,
+    };
+    let mut dma_buf = [0u16; SCAN_BUF_LEN];
+    let mut adc = adc.into_ring_buffered(p.DMA1_CH1, &mut dma_buf, &sequence, trigger);
+    start_scan_timer();
+    adc.start();
 
     let adc_fut = async {
+        let mut block = [0u16; SCAN_BLOCK * SCAN_LEN];
+        // the watchdog thresholds are converted with the reference of the previous samples
+        let mut last = Sample::default();
         loop {
-            let mut samples = [0u16; 6];
-            for i in 0..6 {
-                let sample = match i {
-                    0 => adc.read(&mut p.PA0).await,
-                    1 => adc.read(&mut p.PA1).await,
-                    2 => adc.read(&mut p.PA2).await,
-                    3 => adc.read(&mut p.PA3).await,
-                    4 => adc.read(&mut vrefint).await,
-                    5 => adc.read(&mut p.PB1).await,
-                    _ => 0,
+            watchdog::check_in(watchdog::Task::Adc, now_ms());
+            if adc.read(&mut block).await.is_err() {
+                warn!("adc overrun, samples dropped");
+                continue;
+            }
+
+            // the interrupt handler already shut the load down
+            if let Some((index, value)) = AWD_TRIP.lock(|trip| trip.take()) {
+                if index == SCAN_V {
+                    error!(
+                        "analog watchdog tripped on the input voltage, raw value {}",
+                        value
+                    );
+                } else {
+                    error!(
+                        "analog watchdog tripped on channel {}, raw value {}",
+                        index, value
+                    );
+                }
+                trip(FAULT_AWD).await;
+            }
+
+            let state = LOAD_STATE.lock().await;
+            let armed = state.sdn == 0 && state.fault == 0 && last.cal > 0;
+            drop(state);
+            let awd = SETTINGS.lock().await.awd;
+
+            let mut sum = [0u32; SCAN_LEN];
+            for scan in block.chunks(SCAN_LEN) {
+                for (acc, &sample) in sum.iter_mut().zip(scan.iter()) {
+                    *acc += sample as u32;
+                }
+            }
+
+            // the scans of the block are 1 / SCAN_HZ apart, the last one just finished
+            let block_end = Instant::now().as_micros();
+            let mut step = LOAD_STEP.lock(|step| step.take());
+            let mut stats = STATISTICS.lock().await;
+            let mut capture = CAPTURE.lock().await;
+            for (i, scan) in block.chunks(SCAN_LEN).enumerate() {
+                let sample = scan_sample(scan);
+                let currents = sample.currents();
+                let voltage = sample.voltage();
+                stats.add(&currents, voltage);
+
+                let scan_time = block_end
+                    .saturating_sub((SCAN_BLOCK - 1 - i) as u64 * 1_000_000 / SCAN_HZ as u64);
+                let stepped = step.map_or(false, |time| time <= scan_time);
+                if stepped {
+                    step = None;
+                }
+                let point = CapturePoint {
+                    voltage,
+                    current: currents.iter().sum(),
+                };
+                capture.add(point, stepped);
+            }
+            drop(capture);
+            drop(stats);
+            // a step after the last scan belongs to the next block
+            if let Some(time) = step {
+                LOAD_STEP.lock(|step| {
+                    if step.get().is_none() {
+                        step.set(Some(time));
+                    }
+                });
+            }
+
+            // the limits for the next block, the watchdog compares the 12 most significant
+            // bits of the oversampled result
+            let mut limits = AwdLimits::off();
+            if armed && awd.current > 0 {
+                limits.current = last.current_to_raw(awd.current) >> AWD_SHIFT;
+            }
+            if armed && (awd.voltage_low > 0 || awd.voltage_high > 0) {
+                let low = match awd.voltage_low {
+                    0 => 0,
+                    low => last.voltage_to_raw(low),
+                };
+                let high = match awd.voltage_high {
+                    0 => u16::MAX,
+                    high => last.voltage_to_raw(high),
                 };
-                samples[i] = sample;
+                limits.voltage = Some((low, high));
             }
+            AWD_LIMITS.lock(|cell| cell.set(limits));
+            let mut high = limits.current;
+            if limits.voltage.is_some() {
+                // VREFINT leaves the window on every scan, after the input voltage
+                high = high.min((last.cal as u16 >> AWD_SHIFT) / 2);
+            }
+            adc.set_watchdog_window(0, high);
+
+            let sample = scan_sample(&sum.map(|sum| (sum / SCAN_BLOCK as u32) as u16));
             let mut state = LOAD_STATE.lock().await;
-            state.ch0 = samples[0] as i32;
-            state.ch1 = samples[1] as i32;
-            state.ch2 = samples[2] as i32;
-            state.ch3 = samples[3] as i32;
-            state.cal = samples[4] as i32;
-            state.v = samples[5] as i32;
+            state.ch0 = sample.ch[0];
+            state.ch1 = sample.ch[1];
+            state.ch2 = sample.ch[2];
+            state.ch3 = sample.ch[3];
+            state.v = sample.v;
+            state.cal = sample.cal;
+            state.ts = (sum[SCAN_TS] / SCAN_BLOCK as u32) as i32;
             drop(state);
+
+            if sample.cal > 0 {
+                last = sample;
+            }
+            SAMPLES.signal(sample);
         }
     };
 
-    unwrap!(spawner.spawn(load_control_channel(led1, eload_sdn, pwm, dac_spi, dac_cs0, dac_cs1, dac_cs2, dac_cs3)));
-    unwrap!(spawner.spawn(temp_monitoring_task(i2c)));
+    unwrap!(spawner.spawn(load_control_channel(led1, eload_sdn)));
+    unwrap!(spawner.spawn(temp_monitoring_task(i2c, pwm)));
+    unwrap!(spawner.spawn(host_watchdog_task()));
+
+    let wdg = IndependentWatchdog::new(p.IWDG, WATCHDOG_TIMEOUT_US);
+    unwrap!(spawner.spawn(watchdog_task(wdg)));
 
     let protobuf_rpc_fut = async {
         loop {
@@ -232,20 +941,74 @@ async fn main(spawner: Spawner) {
             info!("Connected");
             let _ = json_rpc(&mut class_usb_ctrl).await;
             info!("Disconnected");
+            HOST_LOST.signal(());
+        }
+    };
+
+    let scpi_fut = async {
+        loop {
+            class_usb_scpi.wait_connection().await;
+            info!("SCPI connected");
+            let _ = scpi(&mut class_usb_scpi).await;
+            info!("SCPI disconnected");
+            HOST_LOST.signal(());
+        }
+    };
+
+    let usbtmc_fut = async {
+        loop {
+            class_usb_tmc.wait_connection().await;
+            info!("USBTMC connected");
+            let _ = usbtmc(&mut class_usb_tmc).await;
+            info!("USBTMC disconnected");
+            HOST_LOST.signal(());
         }
     };
 
-    let _ = join3(usb_fut, protobuf_rpc_fut, adc_fut).await;
+    let _ = join4(
+        usb_fut,
+        protobuf_rpc_fut,
+        join(scpi_fut, usbtmc_fut),
+        adc_fut,
+    )
+    .await;
+}
+
+// temperature sensor reading in 0.0625°C per LSB to m°C
+fn temp_millicelsius(raw: i32) -> i32 {
+    raw as i16 as i32 * 625 / 10
+}
+
+// MCU temperature in m°C from the internal sensor, if it is calibrated and was measured
+fn mcu_temp(state: &LoadState) -> Option<i32> {
+    let factory = FactoryCalibration::read();
+    if !factory.is_valid() || state.cal <= 0 {
+        return None;
+    }
+    Some(factory.temperature(state.ts, state.cal))
 }
 
 #[embassy_executor::task]
-async fn temp_monitoring_task(mut i2c: I2c<'static, I2C1>) {
+async fn temp_monitoring_task(mut i2c: I2c<'static, I2C1>, mut pwm: SimplePwm<'static, TIM2>) {
+    let mut min_pwm = 0;
+    let mut derating = thermal::FULL_SCALE;
+    let mut failures = 0;
     loop {
+        watchdog::check_in(watchdog::Task::Temperature, now_ms());
         let mut data = [0u8; 2];
         if let Err(e) = i2c.blocking_read(0x48, &mut data) {
             error!("i2c error: {:?}", e);
+            failures += 1;
+            // without a temperature the over-temperature protection doesn't work
+            let latched = LOAD_STATE.lock().await.fault & FAULT_TEMP_SENSOR != 0;
+            if failures >= TEMP_SENSOR_RETRIES && !latched {
+                error!("temperature sensor not responding, shutting down the load");
+                trip(FAULT_TEMP_SENSOR).await;
+            }
+            Timer::after_millis(TEMP_SENSOR_RETRY_MS).await;
             continue;
         }
+        failures = 0;
 
         let mut temp_data = ((data[0] as u16) << 4) | ((data[1] as u16) >> 4);
 
@@ -255,122 +1018,950 @@ async fn temp_monitoring_task(mut i2c: I2c<'static, I2C1>) {
 
         info!("read temp: {}", temp_data);
 
+        let temp_mc = temp_millicelsius(temp_data as i32);
+        let settings = SETTINGS.lock().await;
+        let duty = settings.fan_pwm(temp_mc).max(min_pwm).clamp(0, 100);
+        let thermal_state = settings.thermal.state(temp_mc);
+        let scale = settings.thermal.derating(temp_mc);
+        drop(settings);
+        pwm.set_duty(
+            PWMChannel::Ch2,
+            (pwm.get_max_duty() as u32 * duty as u32 / 100) as u16,
+        );
+
+        if scale != derating {
+            derating = scale;
+            DERATING.signal(derating);
+        }
+
         let mut status = LOAD_STATE.lock().await;
         status.temp = temp_data as i32;
+        status.thermal = thermal_state as i32;
+        let tripped = thermal_state == ThermalState::Tripped && status.fault & FAULT_OVER_TEMP == 0;
+        let active = status.sdn == 0;
+        let mcu_temp_mc = mcu_temp(&status);
         drop(status);
 
-        Timer::after_millis(5000).await;
+        // the MCU sits on the same board, a large difference points to a broken sensor
+        if let Some(mcu_temp_mc) = mcu_temp_mc {
+            if (mcu_temp_mc - temp_mc).abs() > MCU_TEMP_TOLERANCE {
+                warn!(
+                    "board temperature {} m°C doesn't match the MCU temperature {} m°C",
+                    temp_mc, mcu_temp_mc
+                );
+            }
+        }
+
+        if tripped {
+            error!("over-temperature, shutting down the load");
+            trip(FAULT_OVER_TEMP).await;
+        }
+
+        // the temperature is only polled slowly while the load is shut down
+        let timeout = Timer::after_millis(if active { 250 } else { 5000 });
+        let fan_update = FAN_PWM.wait();
+        pin_mut!(timeout);
+        pin_mut!(fan_update);
+        if let Either::Right((duty, _)) = select(timeout, fan_update).await {
+            min_pwm = duty;
+        }
     }
 }
 
+// latches a fault and shuts the load down, a running sequence is ended as well
+async fn trip(fault: i32) {
+    LOAD_STATE.lock().await.fault |= fault;
+    let control = LoadControl {
+        sdn: Some(1),
+        mode: Mode::Current([0; 4]),
+    };
+    LOAD_CONTROL.send(control).await;
+}
+
 #[embassy_executor::task]
-async fn load_control_channel(
-    mut led1: Output<'static>,
-    mut sdn: Output<'static>,
-    mut pwm: SimplePwm<'static, TIM2>,
-    mut dac: Spi<'static, SPI1, DMA1_CH3, DMA1_CH2>,
-    mut cs0: Output<'static>,
-    mut cs1: Output<'static>,
-    mut cs2: Output<'static>,
-    mut cs3: Output<'static>,
-) {
-    let mut buf = [0u8; 3];
+async fn host_watchdog_task() {
     loop {
-        let control = LOAD_CONTROL.receive().await;
-
-        match control.sdn {
-            0 => {
-                sdn.set_low();
-                led1.set_low();
-            }
-            _ => {
-                sdn.set_high();
-                led1.set_high();
+        let timeout = SETTINGS.lock().await.comm_timeout;
+        let heartbeat = async {
+            HEARTBEAT.wait().await;
+            None
+        };
+        let lost = async {
+            HOST_LOST.wait().await;
+            Some(FAULT_HOST_LOST)
+        };
+        // 0 disables the timeout
+        let expired = async {
+            match timeout {
+                0 => pending().await,
+                _ => {
+                    Timer::after_millis(timeout as u64).await;
+                    Some(FAULT_HOST_TIMEOUT)
+                }
             }
         };
+        pin_mut!(heartbeat);
+        pin_mut!(lost);
+        pin_mut!(expired);
 
-        // set DAC
-        let cs = [&mut cs0, &mut cs1, &mut cs2, &mut cs3];
-        let dac_val = [control.dac0, control.dac1, control.dac2, control.dac3];
-
-        for i in 0..4 {
-            cs[i].set_low();
-            let data = (dac_val[i] & 0xffff) << 6;
-
-            buf[0] = ((data & 0x00ff0000) >> 16) as u8;
-            buf[1] = ((data & 0x0000ff00) >> 8) as u8;
-            buf[2] =  (data & 0x000000ff) as u8;
+        let fault = match select(heartbeat, select(lost, expired)).await {
+            Either::Left((fault, _)) => fault,
+            Either::Right((Either::Left((fault, _)) | Either::Right((fault, _)), _)) => fault,
+        };
+        if let Some(fault) = fault {
+            let state = LOAD_STATE.lock().await;
+            let (on, unattended) = (state.sdn == 0, state.unattended);
+            drop(state);
+            if on && unattended {
+                warn!("host went away, the running list or discharge test continues");
+            } else if on {
+                error!("host went away, shutting down the load");
+                trip(fault).await;
+            }
+        }
+    }
+}
 
-            let _ = dac.write(&mut buf).await;
+// reports a suspended or disabled bus to the host watchdog, only after the device was
+// configured, so a load that is switched on by the power-on defaults runs without a host
+struct UsbLinkHandler {
+    configured: bool,
+}
 
-            cs[i].set_high();
+impl UsbLinkHandler {
+    fn lost(&mut self) {
+        if self.configured {
+            self.configured = false;
+            HOST_LOST.signal(());
         }
+    }
+}
 
+impl embassy_usb::Handler for UsbLinkHandler {
+    fn enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.lost();
+        }
+    }
 
-        // set PWM
-        pwm.set_duty(
-            PWMChannel::Ch2,
-            (pwm.get_max_duty() as u32 * control.pwm as u32 / 100) as u16,
-        );
+    fn configured(&mut self, configured: bool) {
+        if configured {
+            self.configured = true;
+        } else {
+            self.lost();
+        }
     }
-}
-struct Disconnected {}
 
-impl From<EndpointError> for Disconnected {
-    fn from(val: EndpointError) -> Self {
-        match val {
-            EndpointError::BufferOverflow => panic!("Buffer overflow"),
-            EndpointError::Disabled => Disconnected {},
+    fn suspended(&mut self, suspended: bool) {
+        if suspended {
+            self.lost();
         }
     }
 }
 
-enum Errors {
-    None = 0,
-    InvalidCommand = 1,
-    ErrorDeserializingRequest = 2,
-    ErrorSerializingResponse = 3,
-    ErrorDeserializingRequestData = 4,
-    ErrorSerializingResponseData = 5,
+fn write_dacs(dac_val: &[i32; 4]) {
+    DACS.lock(|dacs| {
+        if let Some(dacs) = dacs.borrow_mut().as_mut() {
+            dacs.write(dac_val);
+        }
+    });
 }
 
-impl Errors {
-    fn to_string(error: &Errors) -> &'static str {
-        match error {
-            Errors::InvalidCommand => "invalid command",
-            Errors::ErrorDeserializingRequest => "error deserializing request",
-            Errors::ErrorSerializingResponse => "error serializing response",
-            Errors::ErrorDeserializingRequestData => "error deserializing request data",
-            Errors::ErrorSerializingResponseData => "error serializing response data",
-            _ => "unknown error",
-        }
+// raw values of one scan in the conversion order
+fn scan_sample(scan: &[u16]) -> Sample {
+    Sample {
+        ch: [
+            scan[0] as i32,
+            scan[1] as i32,
+            scan[2] as i32,
+            scan[3] as i32,
+        ],
+        v: scan[SCAN_V] as i32,
+        cal: scan[SCAN_CAL] as i32,
     }
 }
-enum Commands {
-    NOP = 0,
-    Control = 1,
-    Status = 2,
+
+// TIM3 update events start the ADC scans
+fn start_scan_timer() {
+    TIM3::enable_and_reset();
+    let reload = TIM3::frequency().0 / SCAN_HZ;
+    TIM3::regs().arr().write(|w| w.set_arr(reload as u16 - 1));
+    TIM3::regs().cr2().modify(|w| w.set_mms(Mms::UPDATE));
+    TIM3::regs().cr1().modify(|w| w.set_cen(true));
 }
 
-impl Commands {
-    fn from_i32(value: i32) -> Option<Commands> {
-        match value {
-            0 => Some(Commands::NOP),
-            1 => Some(Commands::Control),
-            2 => Some(Commands::Status),
-            _ => None,
-        }
-    }
+fn transient_running(config: &TransientConfig) -> bool {
+    TRANSIENT.lock(|transient| {
+        transient
+            .borrow()
+            .as_ref()
+            .is_some_and(|t| t.config == *config)
+    })
 }
 
-impl QResponse<'_> {
-    fn default() -> QResponse<'static> {
-        QResponse {
-            id: 0,
-            error: 0,
-            data: Cow::Borrowed(&[0u8]),
+// starts the generator, a running one with the same configuration keeps its phase and only
+// takes the new settings and derating
+fn start_transient(config: TransientConfig, settings: Settings, derating: i32) {
+    let running = TRANSIENT.lock(|transient| match transient.borrow_mut().as_mut() {
+        Some(t) if t.config == config => {
+            t.settings = settings;
+            t.derating = derating;
+            true
         }
+        _ => false,
+    });
+    if running {
+        return;
     }
+
+    TRANSIENT.lock(|transient| {
+        transient.replace(Some(TransientState {
+            config,
+            generator: Transient::new(config),
+            settings,
+            derating,
+        }))
+    });
+
+    TIM6::enable_and_reset();
+    let reload = TIM6::frequency().0 / transient::TICK_HZ;
+    TIM6::regs().arr().write(|w| w.set_arr(reload as u16 - 1));
+    TIM6::regs().dier().modify(|w| w.set_uie(true));
+    TIM6::regs().cr1().modify(|w| {
+        w.set_opm(false);
+        w.set_cen(true);
+    });
+    unsafe { interrupt::TIM6_DAC.enable() };
+}
+
+fn stop_transient() {
+    interrupt::TIM6_DAC.disable();
+    TIM6::regs().cr1().modify(|w| w.set_cen(false));
+    TRANSIENT.lock(|transient| transient.replace(None));
+}
+
+#[interrupt]
+fn TIM6_DAC() {
+    TIM6::regs().sr().modify(|w| w.set_uif(false));
+
+    let dac_val = TRANSIENT.lock(|transient| {
+        transient.borrow_mut().as_mut().map(|t| {
+            let high = t.generator.is_high();
+            let total = t.generator.tick();
+            if t.generator.is_high() != high {
+                load_step();
+            }
+            let current = thermal::derate(&control::split_current(total), t.derating);
+            t.settings.dac_values(&current)
+        })
+    });
+    if let Some(dac_val) = dac_val {
+        write_dacs(&dac_val);
+    }
+}
+
+enum ControlEvent {
+    Control(LoadControl),
+    Sequence(SequenceCommand),
+    Sample(Sample),
+    Derating(i32),
+    Timeout,
+    // nothing happened for ALIVE_INTERVAL_MS
+    Alive,
+}
+
+async fn next_control_event(needs_samples: bool, deadline: Option<Instant>) -> ControlEvent {
+    let control = async { ControlEvent::Control(LOAD_CONTROL.receive().await) };
+    let sequence = async { ControlEvent::Sequence(SEQUENCE_CONTROL.receive().await) };
+    // only wait for ADC samples if the mode needs them
+    let sample = async {
+        match needs_samples {
+            true => ControlEvent::Sample(SAMPLES.wait().await),
+            false => pending().await,
+        }
+    };
+    let derating = async { ControlEvent::Derating(DERATING.wait().await) };
+    let timeout = async {
+        let alive = Instant::now() + Duration::from_millis(ALIVE_INTERVAL_MS);
+        match deadline {
+            Some(deadline) if deadline <= alive => {
+                Timer::at(deadline).await;
+                ControlEvent::Timeout
+            }
+            _ => {
+                Timer::at(alive).await;
+                ControlEvent::Alive
+            }
+        }
+    };
+    pin_mut!(control);
+    pin_mut!(sequence);
+    pin_mut!(sample);
+    pin_mut!(derating);
+    pin_mut!(timeout);
+
+    match select(
+        select(control, sequence),
+        select(sample, select(derating, timeout)),
+    )
+    .await
+    {
+        Either::Left((Either::Left((event, _)) | Either::Right((event, _)), _)) => event,
+        Either::Right((Either::Left((event, _)), _)) => event,
+        Either::Right((
+            Either::Right((Either::Left((event, _)) | Either::Right((event, _)), _)),
+            _,
+        )) => event,
+    }
+}
+
+async fn snapshot() -> Snapshot {
+    let soa = SOA_STATUS.lock().await.state as i32;
+    let state = LOAD_STATE.lock().await;
+    Snapshot {
+        fault: state.fault,
+        mode: state.mode,
+        list_state: state.list_state,
+        list_step: state.list_step,
+        list_loop: state.list_loop,
+        thermal: state.thermal,
+        soa,
+        sdn: state.sdn,
+    }
+}
+
+// switches to the mode of a sequence step, the voltage regulator keeps its state while
+// the setpoint is changed within a step
+fn apply_target(mode: &mut Mode, target: &list::Target, settings: &Settings) {
+    if let (StepMode::Voltage, Mode::Voltage(cv)) = (target.mode, &mut *mode) {
+        if !target.new_step {
+            cv.voltage = target.setpoint;
+            return;
+        }
+    }
+
+    *mode = match target.mode {
+        StepMode::Current => Mode::Current(control::split_current(target.setpoint)),
+        StepMode::Voltage => Mode::Voltage(CvController::new(
+            target.setpoint,
+            settings.max_current.iter().sum(),
+            CvController::DEFAULT_KP,
+            CvController::DEFAULT_KI,
+        )),
+        StepMode::Resistance => Mode::Resistance(target.setpoint),
+        StepMode::Power => Mode::Power(target.setpoint),
+    };
+}
+
+#[embassy_executor::task]
+async fn load_control_channel(mut led1: Output<'static>, mut sdn: Output<'static>) {
+    let mut mode = Mode::Current([0; 4]);
+    let mut sequence: Option<Sequence> = None;
+    let mut deadline: Option<Instant> = None;
+    let mut last_sample = Instant::now();
+    // latest voltage for the resistance and power modes
+    let mut latest: Option<Sample> = None;
+    let mut derating = thermal::FULL_SCALE;
+    let mut soa = SoaMonitor::new();
+    // the SOA limiter checks every sample while the load is switched on
+    let mut load_on = false;
+    let mut events = EventDetector::default();
+    loop {
+        watchdog::check_in(watchdog::Task::Control, now_ms());
+        LOAD_STATE.lock().await.unattended = sequence.as_ref().is_some_and(Sequence::unattended);
+        // changes of the last step, or of other tasks since then
+        events.update(snapshot().await, |event, value| {
+            let _ = EVENTS.try_send(Event {
+                event,
+                value,
+                time: now_ms(),
+            });
+        });
+        let needs_samples = load_on
+            || mode.is_regulated()
+            || sequence.as_ref().map_or(false, |s| s.needs_samples());
+        let event = next_control_event(needs_samples, deadline).await;
+
+        let mut aborted = None;
+        let sample = match event {
+            ControlEvent::Control(control) => {
+                match control.sdn {
+                    None => {}
+                    Some(0) => {
+                        sdn.set_low();
+                        led1.set_low();
+                        load_on = true;
+                    }
+                    Some(_) => {
+                        sdn.set_high();
+                        led1.set_high();
+                        load_on = false;
+                    }
+                };
+                // the timer interrupt must not write the DACs anymore, unless it keeps
+                // running the same transient
+                if !matches!(&control.mode, Mode::Transient(config) if transient_running(config)) {
+                    stop_transient();
+                }
+                mode = control.mode;
+                load_step();
+                // new setpoints release clamped channels
+                soa.reset();
+                let mut soa_status = SOA_STATUS.lock().await;
+                if soa_status.state == SoaState::Clamped {
+                    soa_status.state = SoaState::Ok;
+                }
+                drop(soa_status);
+                // setting a mode by hand ends a running sequence
+                if let Some(running) = sequence.take() {
+                    aborted = Some(running);
+                    deadline = None;
+                }
+                let mut state = LOAD_STATE.lock().await;
+                state.mode = mode.id();
+                if let Some(value) = control.sdn {
+                    state.sdn = (value != 0) as i32;
+                }
+                drop(state);
+                last_sample = Instant::now();
+                None
+            }
+            ControlEvent::Sequence(command) => {
+                let now = Instant::now().as_micros();
+                match command {
+                    SequenceCommand::ListRun { count, loops } => {
+                        stop_transient();
+                        aborted =
+                            sequence.replace(Sequence::List(ListRunner::new(count, loops, now)));
+                    }
+                    SequenceCommand::Sweep(config) => {
+                        stop_transient();
+                        let mut results = SWEEP_RESULTS.lock().await;
+                        results.state = SweepState::Running;
+                        results.count = 0;
+                        drop(results);
+                        aborted = sequence.replace(Sequence::Sweep(Sweep::new(config, now)));
+                    }
+                    SequenceCommand::Ocp(config) => {
+                        stop_transient();
+                        *OCP_RESULT.lock().await = OcpResult {
+                            state: OcpState::Running,
+                            ..OcpResult::default()
+                        };
+                        aborted = sequence.replace(Sequence::Ocp(OcpTest::new(config, now)));
+                    }
+                    SequenceCommand::Discharge(config) => {
+                        stop_transient();
+                        *DISCHARGE_STATUS.lock().await = DischargeStatus::running();
+                        aborted =
+                            sequence.replace(Sequence::Discharge(Discharge::new(config, now)));
+                    }
+                    SequenceCommand::Mppt(config) => {
+                        stop_transient();
+                        *MPPT_STATUS.lock().await = MpptStatus::starting();
+                        aborted = sequence.replace(Sequence::Mppt(Mppt::new(config, now)));
+                    }
+                    SequenceCommand::IvCurve(config) => {
+                        stop_transient();
+                        let mut results = SWEEP_RESULTS.lock().await;
+                        results.state = SweepState::Running;
+                        results.count = 0;
+                        drop(results);
+                        aborted = sequence.replace(Sequence::IvCurve(IvCurve::new(config, now)));
+                    }
+                    SequenceCommand::Pause => {
+                        if let Some(Sequence::List(runner)) = sequence.as_mut() {
+                            runner.pause(now);
+                        }
+                    }
+                    SequenceCommand::Resume => {
+                        if let Some(Sequence::List(runner)) = sequence.as_mut() {
+                            runner.resume(now);
+                        }
+                    }
+                    SequenceCommand::Abort => {
+                        if let Some(running) = sequence.take() {
+                            mode = Mode::Current([0; 4]);
+                            aborted = Some(running);
+                            deadline = None;
+                        }
+                    }
+                }
+                None
+            }
+            ControlEvent::Sample(sample) => Some(sample),
+            ControlEvent::Derating(value) => {
+                derating = value;
+                // a running transient keeps its phase
+                if let Mode::Transient(_) = mode {
+                    TRANSIENT.lock(|transient| {
+                        if let Some(t) = transient.borrow_mut().as_mut() {
+                            t.derating = derating;
+                        }
+                    });
+                    continue;
+                }
+                None
+            }
+            ControlEvent::Timeout => None,
+            ControlEvent::Alive => continue,
+        };
+
+        let now = Instant::now();
+        let dt_us = now.duration_since(last_sample).as_micros() as i64;
+        if sample.is_some() {
+            last_sample = now;
+            latest = sample;
+        }
+
+        // samples go to the running sequence first, so a collapsed source
+        // is shut down before anything else is done
+        if let (Some(running), Some(s)) = (&mut sequence, sample) {
+            let now_us = now.as_micros();
+            let (voltage, current) = (s.voltage(), s.total_current());
+            let (finished, shutdown) = match running {
+                Sequence::List(_) => (false, false),
+                Sequence::Sweep(sweep) => {
+                    sweep.add_sample(now_us, voltage, current);
+                    (false, false)
+                }
+                Sequence::Ocp(test) => {
+                    let tripped = test.check(now_us, voltage, &mut *OCP_RESULT.lock().await);
+                    (tripped, tripped)
+                }
+                Sequence::Discharge(discharge) => {
+                    let status = &mut *DISCHARGE_STATUS.lock().await;
+                    (
+                        discharge.add_sample(now_us, voltage, current, status),
+                        false,
+                    )
+                }
+                Sequence::Mppt(mppt) => {
+                    mppt.add_sample(now_us, voltage, current);
+                    (false, false)
+                }
+                Sequence::IvCurve(curve) => {
+                    curve.add_sample(now_us, voltage, current);
+                    (false, false)
+                }
+            };
+
+            if shutdown {
+                sdn.set_high();
+                led1.set_high();
+                load_on = false;
+                LOAD_STATE.lock().await.sdn = 1;
+            }
+            if finished {
+                mode = Mode::Current([0; 4]);
+                sequence = None;
+                deadline = None;
+                LOAD_STATE.lock().await.mode = mode.id();
+            }
+        }
+
+        if let (true, Some(s)) = (load_on, sample) {
+            let limits = SETTINGS.lock().await.soa;
+            let temp = temp_millicelsius(LOAD_STATE.lock().await.temp);
+            if let Some(violation) =
+                soa.check(&limits, now.as_micros(), s.voltage(), &s.currents(), temp)
+            {
+                warn!(
+                    "soa violation channel: {}, point: {}, power: {}, limit: {}",
+                    violation.channel, violation.point, violation.power, violation.limit
+                );
+                // raw DAC codes and the transient generator bypass the setpoints of this task
+                let can_clamp = !matches!(mode, Mode::Raw(_) | Mode::Transient(_));
+                let state = if limits.shutdown || !can_clamp {
+                    sdn.set_high();
+                    led1.set_high();
+                    load_on = false;
+                    stop_transient();
+                    mode = Mode::Current([0; 4]);
+                    if let Some(running) = sequence.take() {
+                        aborted = Some(running);
+                        deadline = None;
+                    }
+                    let mut state = LOAD_STATE.lock().await;
+                    state.sdn = 1;
+                    state.fault |= FAULT_SOA;
+                    state.mode = mode.id();
+                    drop(state);
+                    SoaState::Shutdown
+                } else {
+                    soa.clamp(&violation);
+                    SoaState::Clamped
+                };
+                *SOA_STATUS.lock().await = SoaStatus { state, violation };
+            }
+        }
+
+        match aborted {
+            Some(Sequence::List(_)) => {
+                LOAD_STATE.lock().await.list_state = ListState::Aborted as i32
+            }
+            Some(Sequence::Sweep(_)) => SWEEP_RESULTS.lock().await.state = SweepState::Aborted,
+            Some(Sequence::Ocp(_)) => OCP_RESULT.lock().await.state = OcpState::Aborted,
+            Some(Sequence::Discharge(_)) => {
+                DISCHARGE_STATUS.lock().await.state = DischargeState::Aborted
+            }
+            Some(Sequence::Mppt(_)) => MPPT_STATUS.lock().await.state = MpptState::Aborted,
+            Some(Sequence::IvCurve(_)) => SWEEP_RESULTS.lock().await.state = SweepState::Aborted,
+            None => {}
+        }
+
+        let settings = SETTINGS.lock().await;
+        if let Some(running) = &mut sequence {
+            let now_us = now.as_micros();
+            let target = match running {
+                Sequence::List(runner) => runner.poll(&LIST_STEPS.lock().await[..], now_us),
+                Sequence::Sweep(sweep) => {
+                    let temp = temp_millicelsius(LOAD_STATE.lock().await.temp);
+                    sweep.poll(now_us, temp, &mut *SWEEP_RESULTS.lock().await)
+                }
+                Sequence::Ocp(test) => test.poll(now_us, &mut *OCP_RESULT.lock().await),
+                Sequence::Discharge(discharge) => Some(discharge.poll()),
+                Sequence::Mppt(mppt) => Some(mppt.poll(now_us, &mut *MPPT_STATUS.lock().await)),
+                Sequence::IvCurve(curve) => {
+                    let temp = temp_millicelsius(LOAD_STATE.lock().await.temp);
+                    curve.poll(now_us, temp, &mut *SWEEP_RESULTS.lock().await)
+                }
+            };
+            let finished = target.is_none();
+            match target {
+                Some(target) => {
+                    apply_target(&mut mode, &target, &settings);
+                    deadline = target.deadline.map(Instant::from_micros);
+                    if target.new_step {
+                        load_step();
+                    }
+                }
+                None => {
+                    mode = Mode::Current([0; 4]);
+                    deadline = None;
+                }
+            }
+
+            let mut state = LOAD_STATE.lock().await;
+            state.mode = mode.id();
+            if let Sequence::List(runner) = running {
+                state.list_state = runner.state as i32;
+                state.list_step = runner.index as i32;
+                state.list_loop = runner.loop_count;
+            }
+            drop(state);
+
+            if finished {
+                sequence = None;
+            }
+        }
+
+        if let Mode::Transient(config) = mode {
+            // samples only feed the SOA limiter, the generator is only restarted for a new
+            // configuration
+            if sample.is_none() {
+                start_transient(config, *settings, derating);
+            }
+            continue;
+        }
+
+        let current = match &mut mode {
+            Mode::Raw(_) | Mode::Transient(_) => [0; 4],
+            Mode::Current(current) => *current,
+            Mode::Voltage(cv) => {
+                // the controller starts from zero current and only steps on new samples
+                let total = sample.map_or(cv.output(), |s| cv.update(s.voltage(), dt_us));
+                control::split_current(total)
+            }
+            Mode::Resistance(resistance) => {
+                let total = latest.map_or(0, |s| control::cr_current(s.voltage(), *resistance));
+                control::split_current(total)
+            }
+            Mode::Power(power) => {
+                let total = latest.map_or(0, |s| control::cp_current(s.voltage(), *power));
+                control::split_current(total)
+            }
+        };
+        let current = latest.map_or(current, |s| soa.limit(&current, s.voltage()));
+
+        // set DAC
+        let dac_val = if let Mode::Raw(dac) = mode {
+            thermal::derate(&dac, derating)
+        } else {
+            settings.dac_values(&thermal::derate(&current, derating))
+        };
+        drop(settings);
+        trace!("dac values: {:?}", dac_val);
+
+        write_dacs(&dac_val);
+    }
+}
+struct Disconnected {}
+
+impl From<EndpointError> for Disconnected {
+    fn from(val: EndpointError) -> Self {
+        match val {
+            // the packets are written in chunks of the max packet size and the readers drop
+            // the frame or line of a packet that didn't fit, so this can't come from the host
+            EndpointError::BufferOverflow | EndpointError::Disabled => Disconnected {},
+        }
+    }
+}
+
+enum Errors {
+    None = 0,
+    InvalidCommand = 1,
+    ErrorDeserializingRequest = 2,
+    ErrorSerializingResponse = 3,
+    ErrorDeserializingRequestData = 4,
+    ErrorSerializingResponseData = 5,
+    InvalidSettings = 6,
+    ErrorWritingSettings = 7,
+    InvalidParameter = 8,
+    InvalidMode = 9,
+    InvalidFrame = 10,
+}
+
+impl Errors {
+    fn to_string(error: &Errors) -> &'static str {
+        match error {
+            Errors::InvalidCommand => "invalid command",
+            Errors::ErrorDeserializingRequest => "error deserializing request",
+            Errors::ErrorSerializingResponse => "error serializing response",
+            Errors::ErrorDeserializingRequestData => "error deserializing request data",
+            Errors::ErrorSerializingResponseData => "error serializing response data",
+            Errors::InvalidSettings => "invalid settings",
+            Errors::ErrorWritingSettings => "error writing settings",
+            Errors::InvalidParameter => "invalid parameter",
+            Errors::InvalidMode => "not possible in the current mode",
+            Errors::InvalidFrame => "invalid frame",
+            _ => "unknown error",
+        }
+    }
+}
+enum Commands {
+    NOP = 0,
+    Control = 1,
+    Status = 2,
+    Calibration = 3,
+    SetCalibration = 4,
+    Limits = 5,
+    SetLimits = 6,
+    FanCurve = 7,
+    SetFanCurve = 8,
+    Defaults = 9,
+    SetDefaults = 10,
+    FactoryReset = 11,
+    ConstantVoltage = 12,
+    ConstantResistance = 13,
+    ConstantPower = 14,
+    Transient = 15,
+    TransientTrigger = 16,
+    ListStep = 17,
+    ListRun = 18,
+    ListPause = 19,
+    ListResume = 20,
+    ListAbort = 21,
+    Sweep = 22,
+    SweepAbort = 23,
+    SweepStatus = 24,
+    SweepPoint = 25,
+    Ocp = 26,
+    OcpAbort = 27,
+    OcpResult = 28,
+    Discharge = 29,
+    DischargeAbort = 30,
+    DischargeStatus = 31,
+    Mppt = 32,
+    MpptAbort = 33,
+    MpptStatus = 34,
+    IvCurve = 35,
+    ClearFault = 36,
+    Thermal = 37,
+    SetThermal = 38,
+    SoaStatus = 39,
+    Soa = 40,
+    SetSoa = 41,
+    CommTimeout = 42,
+    SetCommTimeout = 43,
+    Info = 44,
+    Awd = 45,
+    SetAwd = 46,
+    Statistics = 47,
+    ResetStatistics = 48,
+    SetStatisticsWindow = 49,
+    Capture = 50,
+    CaptureTrigger = 51,
+    CaptureStatus = 52,
+    CaptureData = 53,
+    CaptureAbort = 54,
+    McuCalibration = 55,
+    RawState = 56,
+    Subscribe = 57,
+}
+
+impl Commands {
+    fn from_i32(value: i32) -> Option<Commands> {
+        match value {
+            0 => Some(Commands::NOP),
+            1 => Some(Commands::Control),
+            2 => Some(Commands::Status),
+            3 => Some(Commands::Calibration),
+            4 => Some(Commands::SetCalibration),
+            5 => Some(Commands::Limits),
+            6 => Some(Commands::SetLimits),
+            7 => Some(Commands::FanCurve),
+            8 => Some(Commands::SetFanCurve),
+            9 => Some(Commands::Defaults),
+            10 => Some(Commands::SetDefaults),
+            11 => Some(Commands::FactoryReset),
+            12 => Some(Commands::ConstantVoltage),
+            13 => Some(Commands::ConstantResistance),
+            14 => Some(Commands::ConstantPower),
+            15 => Some(Commands::Transient),
+            16 => Some(Commands::TransientTrigger),
+            17 => Some(Commands::ListStep),
+            18 => Some(Commands::ListRun),
+            19 => Some(Commands::ListPause),
+            20 => Some(Commands::ListResume),
+            21 => Some(Commands::ListAbort),
+            22 => Some(Commands::Sweep),
+            23 => Some(Commands::SweepAbort),
+            24 => Some(Commands::SweepStatus),
+            25 => Some(Commands::SweepPoint),
+            26 => Some(Commands::Ocp),
+            27 => Some(Commands::OcpAbort),
+            28 => Some(Commands::OcpResult),
+            29 => Some(Commands::Discharge),
+            30 => Some(Commands::DischargeAbort),
+            31 => Some(Commands::DischargeStatus),
+            32 => Some(Commands::Mppt),
+            33 => Some(Commands::MpptAbort),
+            34 => Some(Commands::MpptStatus),
+            35 => Some(Commands::IvCurve),
+            36 => Some(Commands::ClearFault),
+            37 => Some(Commands::Thermal),
+            38 => Some(Commands::SetThermal),
+            39 => Some(Commands::SoaStatus),
+            40 => Some(Commands::Soa),
+            41 => Some(Commands::SetSoa),
+            42 => Some(Commands::CommTimeout),
+            43 => Some(Commands::SetCommTimeout),
+            44 => Some(Commands::Info),
+            45 => Some(Commands::Awd),
+            46 => Some(Commands::SetAwd),
+            47 => Some(Commands::Statistics),
+            48 => Some(Commands::ResetStatistics),
+            49 => Some(Commands::SetStatisticsWindow),
+            50 => Some(Commands::Capture),
+            51 => Some(Commands::CaptureTrigger),
+            52 => Some(Commands::CaptureStatus),
+            53 => Some(Commands::CaptureData),
+            54 => Some(Commands::CaptureAbort),
+            55 => Some(Commands::McuCalibration),
+            56 => Some(Commands::RawState),
+            57 => Some(Commands::Subscribe),
+            _ => None,
+        }
+    }
+}
+
+impl QResponse<'_> {
+    fn default() -> QResponse<'static> {
+        QResponse {
+            id: 0,
+            error: 0,
+            data: Cow::Borrowed(&[0u8]),
+            kind: MSG_RESPONSE,
+        }
+    }
+}
+
+// Validates, applies and persists modified settings
+async fn update_settings(update: impl FnOnce(&mut Settings)) -> Result<(), Errors> {
+    let mut settings = SETTINGS.lock().await;
+    let mut new_settings = *settings;
+    update(&mut new_settings);
+    if !new_settings.is_valid() {
+        return Err(Errors::InvalidSettings);
+    }
+    *settings = new_settings;
+    drop(settings);
+
+    let mut eeprom = EEPROM.lock().await;
+    let flash = eeprom.as_mut().ok_or(Errors::ErrorWritingSettings)?;
+    settings::store(flash, &new_settings).map_err(|e| {
+        error!("error storing settings: {:?}", e);
+        Errors::ErrorWritingSettings
+    })
+}
+
+async fn list_active() -> bool {
+    let state = LOAD_STATE.lock().await.list_state;
+    state == ListState::Running as i32 || state == ListState::Paused as i32
+}
+
+async fn send_list_command(command: SequenceCommand) -> Result<(), Errors> {
+    if !list_active().await {
+        return Err(Errors::InvalidMode);
+    }
+    SEQUENCE_CONTROL.send(command).await;
+    Ok(())
+}
+
+// the state in engineering units, for the status and the pushed states
+async fn load_state() -> QState {
+    let state = LOAD_STATE.lock().await;
+    // the same conversion as for the regulated modes, with the factory calibrated VREFINT
+    let sample = Sample {
+        ch: [state.ch0, state.ch1, state.ch2, state.ch3],
+        cal: state.cal,
+        v: state.v,
+    };
+    let currents = sample.currents();
+    let voltage = sample.voltage();
+    let power = voltage as i64 * sample.total_current() as i64 / 1000;
+    QState {
+        current0: currents[0],
+        current1: currents[1],
+        current2: currents[2],
+        current3: currents[3],
+        voltage,
+        power: power as i32,
+        temp: temp_millicelsius(state.temp),
+        sdn: state.sdn,
+        mode: state.mode,
+        list_state: state.list_state,
+        list_step: state.list_step,
+        list_loop: state.list_loop,
+        thermal: state.thermal,
+        fault: state.fault,
+    }
+}
+
+// serializes the data of a response with its length prefix, returns the number of bytes
+fn serialize_response<M: MessageWrite>(msg: &M, response_data: &mut [u8]) -> Result<usize, Errors> {
+    quick_protobuf::serialize_into_slice(msg, response_data)
+        .map_err(|_| Errors::ErrorSerializingResponseData)?;
+    Ok(sizeof_len(msg.get_size()))
+}
+
+// the load stays off until a latched fault is cleared
+async fn check_switch_on(sdn: i32) -> Result<(), Errors> {
+    if sdn == 0 && LOAD_STATE.lock().await.fault != 0 {
+        return Err(Errors::InvalidMode);
+    }
+    Ok(())
+}
+
+// the sequences drive the setpoints but don't switch the load on, a test of a load that is
+// off or has a latched fault would only report bogus results
+async fn check_sequence_start() -> Result<(), Errors> {
+    let state = LOAD_STATE.lock().await;
+    if state.sdn != 0 || state.fault != 0 {
+        return Err(Errors::InvalidMode);
+    }
+    Ok(())
 }
 
 // The response_bytes should be a mutable slice of u8, not a slice of a mutable slice.
@@ -378,7 +1969,7 @@ async fn process_request<'a>(
     request: &QRequest<'_>,
     response: &mut QResponse<'_>,
 ) -> Result<usize, Errors> {
-    let mut response_data = [0u8; 32];
+    let mut response_data = [0u8; RESPONSE_DATA_LEN];
     let mut response_len = 0;
     let error = Errors::None as i32;
 
@@ -396,23 +1987,35 @@ async fn process_request<'a>(
                 .map_err(|_| Errors::ErrorDeserializingRequestData)?;
 
             info!(
-                "receiving ctrl sdn: {}, pwm: {}, dac0: {}, dac1: {}, dac2: {}, dac3: {}",
-                cmd.sdn, cmd.pwm, cmd.dac0, cmd.dac1, cmd.dac2, cmd.dac3
+                "receiving ctrl sdn: {}, pwm: {}, raw: {}, dac0: {}, dac1: {}, dac2: {}, dac3: {}, current0: {}, current1: {}, current2: {}, current3: {}",
+                cmd.sdn, cmd.pwm, cmd.raw, cmd.dac0, cmd.dac1, cmd.dac2, cmd.dac3, cmd.current0, cmd.current1, cmd.current2, cmd.current3
             );
 
+            check_switch_on(cmd.sdn).await?;
+
+            FAN_PWM.signal(cmd.pwm);
+
+            let mode = if cmd.raw != 0 {
+                Mode::Raw([cmd.dac0, cmd.dac1, cmd.dac2, cmd.dac3])
+            } else {
+                Mode::Current([cmd.current0, cmd.current1, cmd.current2, cmd.current3])
+            };
             let control = LoadControl {
-                sdn: cmd.sdn,
-                pwm: cmd.pwm,
-                dac0: cmd.dac0,
-                dac1: cmd.dac1,
-                dac2: cmd.dac2,
-                dac3: cmd.dac3,
+                sdn: Some(cmd.sdn),
+                mode,
             };
             LOAD_CONTROL.send(control).await;
         }
         Commands::Status => {
+            let qstate = load_state().await;
+
+            info!("sending state - current0: {}, current1: {}, current2: {}, current3: {}, voltage: {}, power: {}, temp: {}, sdn: {}, mode: {}, list_state: {}, list_step: {}, list_loop: {}, thermal: {}, fault: {}", qstate.current0, qstate.current1, qstate.current2, qstate.current3, qstate.voltage, qstate.power, qstate.temp, qstate.sdn, qstate.mode, qstate.list_state, qstate.list_step, qstate.list_loop, qstate.thermal, qstate.fault);
+
+            response_len = serialize_response(&qstate, &mut response_data)?;
+        }
+        Commands::RawState => {
             let state = LOAD_STATE.lock().await;
-            let qstate = QState {
+            let qstate = QRawState {
                 ch0: state.ch0,
                 ch1: state.ch1,
                 ch2: state.ch2,
@@ -420,68 +2023,1382 @@ async fn process_request<'a>(
                 cal: state.cal,
                 v: state.v,
                 temp: state.temp,
-                sdn: state.sdn,
+                ts: state.ts,
             };
             drop(state);
 
-            info!("sending state - ch0: {}, ch1: {}, ch2: {}, ch3: {}, cal: {}, v: {}, temp: {}, sdn: {}", qstate.ch0, qstate.ch1, qstate.ch2, qstate.ch3, qstate.cal, qstate.v, qstate.temp, qstate.sdn);
+            info!(
+                "sending raw state - ch0: {}, ch1: {}, ch2: {}, ch3: {}, cal: {}, v: {}, temp: {}, ts: {}",
+                qstate.ch0, qstate.ch1, qstate.ch2, qstate.ch3, qstate.cal, qstate.v, qstate.temp, qstate.ts
+            );
 
-            response_len = qstate.get_size() + 1 /* varint */;
-            quick_protobuf::serialize_into_slice(&qstate, &mut response_data[..])
-                .map_err(|_| Errors::ErrorSerializingResponseData)?;
+            response_len = serialize_response(&qstate, &mut response_data)?;
         }
-    };
+        Commands::Subscribe => {
+            let cmd: QSubscribe = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
 
-    response.id = request.id;
-    response.error = error;
-    response.data = Cow::Owned(response_data[..response_len].to_vec());
-    debug!(
-        "response.id: {}, response.error:{}, response.data: {:?}",
-        response.id,
-        response.error,
-        response_data[..response_len]
-    );
-    Ok(response_len)
-}
+            info!(
+                "receiving subscribe - interval: {}, events: {}",
+                cmd.interval, cmd.events
+            );
 
-async fn json_rpc<'d, T: Instance + 'd>(
-    class: &mut CdcAcmClass<'d, Driver<'d, T>>,
-) -> Result<(), Disconnected> {
-    let mut request_bytes = [0u8; 64];
-    let mut response_bytes = [0u8; 64];
+            if cmd.interval != 0 && !(MIN_PUSH_INTERVAL..=MAX_PUSH_INTERVAL).contains(&cmd.interval)
+            {
+                return Err(Errors::InvalidParameter);
+            }
+            // only events after the subscription
+            while EVENTS.try_receive().is_ok() {}
+            *SUBSCRIPTION.lock().await = Subscription {
+                interval: cmd.interval as u32,
+                events: cmd.events != 0,
+            };
+        }
+        Commands::Calibration => {
+            let cal = SETTINGS.lock().await.calibration;
+            let qcal = QCalibration {
+                gain0: cal[0].gain,
+                gain1: cal[1].gain,
+                gain2: cal[2].gain,
+                gain3: cal[3].gain,
+                offset0: cal[0].offset,
+                offset1: cal[1].offset,
+                offset2: cal[2].offset,
+                offset3: cal[3].offset,
+            };
 
-    loop {
-        let n = class.read_packet(&mut request_bytes).await?;
+            response_len = serialize_response(&qcal, &mut response_data)?;
+        }
+        Commands::SetCalibration => {
+            let cmd: QCalibration = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
 
-        let mut response = QResponse::default();
+            info!(
+                "receiving calibration gain: {} {} {} {}, offset: {} {} {} {}",
+                cmd.gain0,
+                cmd.gain1,
+                cmd.gain2,
+                cmd.gain3,
+                cmd.offset0,
+                cmd.offset1,
+                cmd.offset2,
+                cmd.offset3
+            );
 
-        let request = match quick_protobuf::deserialize_from_slice(&request_bytes[..n]) {
-            Ok(req) => Some(req),
-            Err(_) => {
-                error!("{}", Errors::to_string(&Errors::ErrorDeserializingRequest));
-                response = QResponse::default();
-                response.error = Errors::ErrorDeserializingRequest as i32;
-                None
-            }
-        };
+            update_settings(|s| {
+                let gain = [cmd.gain0, cmd.gain1, cmd.gain2, cmd.gain3];
+                let offset = [cmd.offset0, cmd.offset1, cmd.offset2, cmd.offset3];
+                for i in 0..4 {
+                    s.calibration[i].gain = gain[i];
+                    s.calibration[i].offset = offset[i];
+                }
+            })
+            .await?;
+        }
+        Commands::Limits => {
+            let max_current = SETTINGS.lock().await.max_current;
+            let qlimits = QLimits {
+                max_current0: max_current[0],
+                max_current1: max_current[1],
+                max_current2: max_current[2],
+                max_current3: max_current[3],
+            };
 
-        // if request is some then we can process the request
-        if request.is_some() {
-            if let Err(e) = process_request(&request.unwrap(), &mut response).await {
-                error!("{}", Errors::to_string(&e));
-                response = QResponse::default();
-                response.error = e as i32;
-            }
+            response_len = serialize_response(&qlimits, &mut response_data)?;
         }
+        Commands::SetLimits => {
+            let cmd: QLimits = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
 
-        let serialized_len = response.get_size() + 1 /* varint */;
-        if let Err(_) = quick_protobuf::serialize_into_slice(&response, &mut response_bytes) {
-            error!("{}", Errors::to_string(&Errors::ErrorSerializingResponse));
-            continue;
+            info!(
+                "receiving limits max_current: {} {} {} {}",
+                cmd.max_current0, cmd.max_current1, cmd.max_current2, cmd.max_current3
+            );
+
+            update_settings(|s| {
+                s.max_current = [
+                    cmd.max_current0,
+                    cmd.max_current1,
+                    cmd.max_current2,
+                    cmd.max_current3,
+                ];
+            })
+            .await?;
+        }
+        Commands::FanCurve => {
+            let curve = SETTINGS.lock().await.fan_curve;
+            let qcurve = QFanCurve {
+                temp0: curve[0].temp,
+                temp1: curve[1].temp,
+                temp2: curve[2].temp,
+                temp3: curve[3].temp,
+                pwm0: curve[0].pwm,
+                pwm1: curve[1].pwm,
+                pwm2: curve[2].pwm,
+                pwm3: curve[3].pwm,
+            };
+
+            response_len = serialize_response(&qcurve, &mut response_data)?;
+        }
+        Commands::SetFanCurve => {
+            let cmd: QFanCurve = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving fan curve temp: {} {} {} {}, pwm: {} {} {} {}",
+                cmd.temp0, cmd.temp1, cmd.temp2, cmd.temp3, cmd.pwm0, cmd.pwm1, cmd.pwm2, cmd.pwm3
+            );
+
+            update_settings(|s| {
+                let temp = [cmd.temp0, cmd.temp1, cmd.temp2, cmd.temp3];
+                let pwm = [cmd.pwm0, cmd.pwm1, cmd.pwm2, cmd.pwm3];
+                for i in 0..4 {
+                    s.fan_curve[i].temp = temp[i];
+                    s.fan_curve[i].pwm = pwm[i];
+                }
+            })
+            .await?;
+        }
+        Commands::Defaults => {
+            let defaults = SETTINGS.lock().await.defaults;
+            let qdefaults = QDefaults {
+                sdn: defaults.sdn,
+                pwm: defaults.pwm,
+                current0: defaults.current[0],
+                current1: defaults.current[1],
+                current2: defaults.current[2],
+                current3: defaults.current[3],
+            };
+
+            response_len = serialize_response(&qdefaults, &mut response_data)?;
         }
+        Commands::SetDefaults => {
+            let cmd: QDefaults = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving defaults sdn: {}, pwm: {}, current: {} {} {} {}",
+                cmd.sdn, cmd.pwm, cmd.current0, cmd.current1, cmd.current2, cmd.current3
+            );
 
-        class
-            .write_packet(&response_bytes[..serialized_len])
+            update_settings(|s| {
+                s.defaults.sdn = cmd.sdn;
+                s.defaults.pwm = cmd.pwm;
+                s.defaults.current = [cmd.current0, cmd.current1, cmd.current2, cmd.current3];
+            })
             .await?;
+        }
+        Commands::FactoryReset => {
+            info!("factory reset");
+            update_settings(|s| *s = Settings::default()).await?;
+        }
+        Commands::ConstantVoltage => {
+            let cmd: QCv = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving cv voltage: {}, max_current: {}, kp: {}, ki: {}",
+                cmd.voltage, cmd.max_current, cmd.kp, cmd.ki
+            );
+
+            if cmd.voltage <= 0 || cmd.max_current <= 0 || cmd.kp < 0 || cmd.ki < 0 {
+                return Err(Errors::InvalidParameter);
+            }
+
+            let kp = if cmd.kp == 0 {
+                CvController::DEFAULT_KP
+            } else {
+                cmd.kp
+            };
+            let ki = if cmd.ki == 0 {
+                CvController::DEFAULT_KI
+            } else {
+                cmd.ki
+            };
+            let control = LoadControl {
+                sdn: None,
+                mode: Mode::Voltage(CvController::new(cmd.voltage, cmd.max_current, kp, ki)),
+            };
+            LOAD_CONTROL.send(control).await;
+        }
+        Commands::ConstantResistance => {
+            let cmd: QCr = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!("receiving cr resistance: {}", cmd.resistance);
+
+            if cmd.resistance <= 0 {
+                return Err(Errors::InvalidParameter);
+            }
+
+            let control = LoadControl {
+                sdn: None,
+                mode: Mode::Resistance(cmd.resistance),
+            };
+            LOAD_CONTROL.send(control).await;
+        }
+        Commands::ConstantPower => {
+            let cmd: QCp = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!("receiving cp power: {}", cmd.power);
+
+            if cmd.power < 0 {
+                return Err(Errors::InvalidParameter);
+            }
+
+            let control = LoadControl {
+                sdn: None,
+                mode: Mode::Power(cmd.power),
+            };
+            LOAD_CONTROL.send(control).await;
+        }
+        Commands::Transient => {
+            let cmd: QTransient = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving transient current_a: {}, current_b: {}, period: {}, duty: {}, rise: {}, fall: {}, trigger: {}",
+                cmd.current_a, cmd.current_b, cmd.period, cmd.duty, cmd.rise, cmd.fall, cmd.trigger
+            );
+
+            let config = TransientConfig {
+                current_a: cmd.current_a,
+                current_b: cmd.current_b,
+                period: cmd.period,
+                duty: cmd.duty,
+                rise: cmd.rise,
+                fall: cmd.fall,
+                trigger: Trigger::from_i32(cmd.trigger).ok_or(Errors::InvalidParameter)?,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+
+            let control = LoadControl {
+                sdn: None,
+                mode: Mode::Transient(config),
+            };
+            LOAD_CONTROL.send(control).await;
+        }
+        Commands::TransientTrigger => {
+            info!("receiving transient trigger");
+
+            let triggered = TRANSIENT.lock(|transient| {
+                transient
+                    .borrow_mut()
+                    .as_mut()
+                    .map(|t| t.generator.trigger())
+                    .is_some()
+            });
+            if !triggered {
+                return Err(Errors::InvalidMode);
+            }
+            load_step();
+        }
+        Commands::ListStep => {
+            let cmd: QListStep = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving list step index: {}, mode: {}, setpoint: {}, dwell: {}, slew: {}",
+                cmd.index, cmd.mode, cmd.setpoint, cmd.dwell, cmd.slew
+            );
+
+            let step = Step {
+                mode: StepMode::from_i32(cmd.mode).ok_or(Errors::InvalidParameter)?,
+                setpoint: cmd.setpoint,
+                dwell: cmd.dwell,
+                slew: cmd.slew,
+            };
+            if !(0..MAX_STEPS as i32).contains(&cmd.index) || !step.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+            // the table can't be changed while it is executed
+            if list_active().await {
+                return Err(Errors::InvalidMode);
+            }
+            LIST_STEPS.lock().await[cmd.index as usize] = step;
+        }
+        Commands::ListRun => {
+            let cmd: QListRun = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving list run count: {}, loops: {}",
+                cmd.count, cmd.loops
+            );
+
+            if !(1..=MAX_STEPS as i32).contains(&cmd.count) || cmd.loops < 0 {
+                return Err(Errors::InvalidParameter);
+            }
+            // all steps have to be uploaded
+            let steps = LIST_STEPS.lock().await;
+            if !steps[..cmd.count as usize].iter().all(|s| s.is_valid()) {
+                return Err(Errors::InvalidParameter);
+            }
+            drop(steps);
+            check_sequence_start().await?;
+
+            let command = SequenceCommand::ListRun {
+                count: cmd.count as usize,
+                loops: cmd.loops,
+            };
+            SEQUENCE_CONTROL.send(command).await;
+        }
+        Commands::ListPause => {
+            info!("receiving list pause");
+            send_list_command(SequenceCommand::Pause).await?;
+        }
+        Commands::ListResume => {
+            info!("receiving list resume");
+            send_list_command(SequenceCommand::Resume).await?;
+        }
+        Commands::ListAbort => {
+            info!("receiving list abort");
+            send_list_command(SequenceCommand::Abort).await?;
+        }
+        Commands::Sweep => {
+            let cmd: QSweep = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving sweep mode: {}, start: {}, stop: {}, points: {}, dwell: {}",
+                cmd.mode, cmd.start, cmd.stop, cmd.points, cmd.dwell
+            );
+
+            let config = SweepConfig {
+                mode: StepMode::from_i32(cmd.mode).ok_or(Errors::InvalidParameter)?,
+                start: cmd.start,
+                stop: cmd.stop,
+                points: cmd.points,
+                dwell: cmd.dwell,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+            check_sequence_start().await?;
+            SEQUENCE_CONTROL.send(SequenceCommand::Sweep(config)).await;
+        }
+        Commands::SweepAbort => {
+            info!("receiving sweep abort");
+
+            if SWEEP_RESULTS.lock().await.state != SweepState::Running {
+                return Err(Errors::InvalidMode);
+            }
+            SEQUENCE_CONTROL.send(SequenceCommand::Abort).await;
+        }
+        Commands::SweepStatus => {
+            let results = SWEEP_RESULTS.lock().await;
+            let status = QSweepStatus {
+                state: results.state as i32,
+                count: results.count as i32,
+            };
+            drop(results);
+
+            info!(
+                "sending sweep status - state: {}, count: {}",
+                status.state, status.count
+            );
+
+            response_len = serialize_response(&status, &mut response_data)?;
+        }
+        Commands::SweepPoint => {
+            let cmd: QSweepPoint = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            let results = SWEEP_RESULTS.lock().await;
+            if !(0..results.count as i32).contains(&cmd.index) {
+                return Err(Errors::InvalidParameter);
+            }
+            let point = results.points[cmd.index as usize];
+            drop(results);
+
+            let qpoint = QSweepPoint {
+                index: cmd.index,
+                setpoint: point.setpoint,
+                voltage: point.voltage,
+                current: point.current,
+                temp: point.temp,
+            };
+
+            info!(
+                "sending sweep point - index: {}, setpoint: {}, voltage: {}, current: {}, temp: {}",
+                qpoint.index, qpoint.setpoint, qpoint.voltage, qpoint.current, qpoint.temp
+            );
+
+            response_len = serialize_response(&qpoint, &mut response_data)?;
+        }
+        Commands::Ocp => {
+            let cmd: QOcp = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving ocp start: {}, stop: {}, step: {}, dwell: {}, threshold: {}, drop: {}",
+                cmd.start, cmd.stop, cmd.step, cmd.dwell, cmd.threshold, cmd.drop
+            );
+
+            let config = OcpConfig {
+                start: cmd.start,
+                stop: cmd.stop,
+                step: cmd.step,
+                dwell: cmd.dwell,
+                threshold: cmd.threshold,
+                drop: cmd.drop,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+            check_sequence_start().await?;
+            SEQUENCE_CONTROL.send(SequenceCommand::Ocp(config)).await;
+        }
+        Commands::OcpAbort => {
+            info!("receiving ocp abort");
+
+            if OCP_RESULT.lock().await.state != OcpState::Running {
+                return Err(Errors::InvalidMode);
+            }
+            SEQUENCE_CONTROL.send(SequenceCommand::Abort).await;
+        }
+        Commands::OcpResult => {
+            let result = OCP_RESULT.lock().await;
+            let qresult = QOcpResult {
+                state: result.state as i32,
+                current: result.current,
+                voltage: result.voltage,
+                time: result.time.min(i32::MAX as u64) as i32,
+            };
+            drop(result);
+
+            info!(
+                "sending ocp result - state: {}, current: {}, voltage: {}, time: {}",
+                qresult.state, qresult.current, qresult.voltage, qresult.time
+            );
+
+            response_len = serialize_response(&qresult, &mut response_data)?;
+        }
+        Commands::Discharge => {
+            let cmd: QDischarge = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving discharge mode: {}, setpoint: {}, cutoff: {}, debounce: {}",
+                cmd.mode, cmd.setpoint, cmd.cutoff, cmd.debounce
+            );
+
+            let config = DischargeConfig {
+                mode: StepMode::from_i32(cmd.mode).ok_or(Errors::InvalidParameter)?,
+                setpoint: cmd.setpoint,
+                cutoff: cmd.cutoff,
+                debounce: cmd.debounce,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+            check_sequence_start().await?;
+            SEQUENCE_CONTROL
+                .send(SequenceCommand::Discharge(config))
+                .await;
+        }
+        Commands::DischargeAbort => {
+            info!("receiving discharge abort");
+
+            if DISCHARGE_STATUS.lock().await.state != DischargeState::Running {
+                return Err(Errors::InvalidMode);
+            }
+            SEQUENCE_CONTROL.send(SequenceCommand::Abort).await;
+        }
+        Commands::DischargeStatus => {
+            let status = DISCHARGE_STATUS.lock().await;
+            let qstatus = QDischargeStatus {
+                state: status.state as i32,
+                elapsed: (status.elapsed / 1000).min(i32::MAX as u64) as i32,
+                charge: status.charge_mah(),
+                energy: status.energy_mwh(),
+            };
+            drop(status);
+
+            info!(
+                "sending discharge status - state: {}, elapsed: {}, charge: {}, energy: {}",
+                qstatus.state, qstatus.elapsed, qstatus.charge, qstatus.energy
+            );
+
+            response_len = serialize_response(&qstatus, &mut response_data)?;
+        }
+        Commands::Mppt => {
+            let cmd: QMppt = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving mppt step: {}, interval: {}",
+                cmd.step, cmd.interval
+            );
+
+            let config = MpptConfig {
+                step: cmd.step,
+                interval: cmd.interval,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+            check_sequence_start().await?;
+            SEQUENCE_CONTROL.send(SequenceCommand::Mppt(config)).await;
+        }
+        Commands::MpptAbort => {
+            info!("receiving mppt abort");
+
+            if !matches!(
+                MPPT_STATUS.lock().await.state,
+                MpptState::Starting | MpptState::Tracking
+            ) {
+                return Err(Errors::InvalidMode);
+            }
+            SEQUENCE_CONTROL.send(SequenceCommand::Abort).await;
+        }
+        Commands::MpptStatus => {
+            let status = MPPT_STATUS.lock().await;
+            let qstatus = QMpptStatus {
+                state: status.state as i32,
+                voltage: status.voltage,
+                current: status.current,
+                power: status.power,
+                voc: status.voc,
+            };
+            drop(status);
+
+            info!(
+                "sending mppt status - state: {}, voltage: {}, current: {}, power: {}, voc: {}",
+                qstatus.state, qstatus.voltage, qstatus.current, qstatus.power, qstatus.voc
+            );
+
+            response_len = serialize_response(&qstatus, &mut response_data)?;
+        }
+        Commands::IvCurve => {
+            let cmd: QIvCurve = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving iv curve points: {}, dwell: {}, min_voltage: {}",
+                cmd.points, cmd.dwell, cmd.min_voltage
+            );
+
+            let config = IvCurveConfig {
+                points: cmd.points,
+                dwell: cmd.dwell,
+                min_voltage: cmd.min_voltage,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+            check_sequence_start().await?;
+            SEQUENCE_CONTROL
+                .send(SequenceCommand::IvCurve(config))
+                .await;
+        }
+        Commands::ClearFault => {
+            info!("receiving clear fault");
+
+            let mut state = LOAD_STATE.lock().await;
+            // can't be cleared while the load is still too hot
+            if state.thermal == ThermalState::Tripped as i32 {
+                return Err(Errors::InvalidMode);
+            }
+            state.fault = 0;
+            drop(state);
+            *SOA_STATUS.lock().await = SoaStatus::default();
+        }
+        Commands::Thermal => {
+            let thermal = SETTINGS.lock().await.thermal;
+            let qthermal = QThermal {
+                warning: thermal.warning,
+                derate: thermal.derate,
+                trip: thermal.trip,
+            };
+
+            response_len = serialize_response(&qthermal, &mut response_data)?;
+        }
+        Commands::SetThermal => {
+            let cmd: QThermal = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving thermal limits warning: {}, derate: {}, trip: {}",
+                cmd.warning, cmd.derate, cmd.trip
+            );
+
+            update_settings(|s| {
+                s.thermal.warning = cmd.warning;
+                s.thermal.derate = cmd.derate;
+                s.thermal.trip = cmd.trip;
+            })
+            .await?;
+        }
+        Commands::SoaStatus => {
+            let status = SOA_STATUS.lock().await;
+            let qstatus = QSoaStatus {
+                state: status.state as i32,
+                channel: status.violation.channel as i32,
+                point: status.violation.point as i32,
+                power: status.violation.power,
+                limit: status.violation.limit,
+            };
+            drop(status);
+
+            info!(
+                "sending soa status - state: {}, channel: {}, point: {}, power: {}, limit: {}",
+                qstatus.state, qstatus.channel, qstatus.point, qstatus.power, qstatus.limit
+            );
+
+            response_len = serialize_response(&qstatus, &mut response_data)?;
+        }
+        Commands::Soa => {
+            let soa = SETTINGS.lock().await.soa;
+            let qsoa = QSoa {
+                time0: soa.points[0].time,
+                time1: soa.points[1].time,
+                time2: soa.points[2].time,
+                time3: soa.points[3].time,
+                power0: soa.points[0].power,
+                power1: soa.points[1].power,
+                power2: soa.points[2].power,
+                power3: soa.points[3].power,
+                max_temp: soa.max_temp,
+                shutdown: soa.shutdown as i32,
+            };
+
+            response_len = serialize_response(&qsoa, &mut response_data)?;
+        }
+        Commands::SetSoa => {
+            let cmd: QSoa = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving soa time: {} {} {} {}, power: {} {} {} {}, max_temp: {}, shutdown: {}",
+                cmd.time0,
+                cmd.time1,
+                cmd.time2,
+                cmd.time3,
+                cmd.power0,
+                cmd.power1,
+                cmd.power2,
+                cmd.power3,
+                cmd.max_temp,
+                cmd.shutdown
+            );
+
+            let time = [cmd.time0, cmd.time1, cmd.time2, cmd.time3];
+            let power = [cmd.power0, cmd.power1, cmd.power2, cmd.power3];
+            // the dissipation is checked on every sample, a shorter pulse would fire late
+            if time.iter().any(|&t| t < SAMPLE_MS) {
+                return Err(Errors::InvalidParameter);
+            }
+            update_settings(|s| {
+                for i in 0..4 {
+                    s.soa.points[i].time = time[i];
+                    s.soa.points[i].power = power[i];
+                }
+                s.soa.max_temp = cmd.max_temp;
+                s.soa.shutdown = cmd.shutdown != 0;
+            })
+            .await?;
+        }
+        Commands::CommTimeout => {
+            let qtimeout = QCommTimeout {
+                timeout: SETTINGS.lock().await.comm_timeout,
+            };
+
+            response_len = serialize_response(&qtimeout, &mut response_data)?;
+        }
+        Commands::SetCommTimeout => {
+            let cmd: QCommTimeout = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!("receiving comm timeout: {}", cmd.timeout);
+
+            update_settings(|s| s.comm_timeout = cmd.timeout).await?;
+            // restart the watchdog with the new timeout
+            HEARTBEAT.signal(());
+        }
+        Commands::Info => {
+            let max_current = SETTINGS.lock().await.max_current.iter().sum();
+            let qinfo = QInfo {
+                reset_cause: *RESET_CAUSE.lock().await as i32,
+                protocol_version: PROTOCOL_VERSION,
+                firmware_version: Cow::Borrowed(env!("CARGO_PKG_VERSION")),
+                git_hash: Cow::Borrowed(env!("GIT_HASH")),
+                build_date: Cow::Borrowed(env!("BUILD_DATE")),
+                hardware_revision: HARDWARE_REVISION,
+                uid: Cow::Borrowed(&embassy_stm32::uid::uid()[..]),
+                num_channels: settings::NUM_CHANNELS as i32,
+                capabilities: CAPABILITIES,
+                max_current,
+                max_list_steps: MAX_STEPS as i32,
+                max_sweep_points: sweep::MAX_POINTS as i32,
+                max_capture_points: capture::MAX_POINTS as i32,
+                max_message: framing::MAX_MESSAGE as i32,
+            };
+
+            info!(
+                "sending info - reset_cause: {}, protocol_version: {}, firmware_version: {}, git_hash: {}, build_date: {}, uid: {}",
+                qinfo.reset_cause,
+                qinfo.protocol_version,
+                env!("CARGO_PKG_VERSION"),
+                env!("GIT_HASH"),
+                env!("BUILD_DATE"),
+                embassy_stm32::uid::uid_hex()
+            );
+
+            response_len = serialize_response(&qinfo, &mut response_data)?;
+        }
+        Commands::Awd => {
+            let awd = SETTINGS.lock().await.awd;
+            let qawd = QAwd {
+                current: awd.current,
+                voltage_low: awd.voltage_low,
+                voltage_high: awd.voltage_high,
+            };
+
+            response_len = serialize_response(&qawd, &mut response_data)?;
+        }
+        Commands::SetAwd => {
+            let cmd: QAwd = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving analog watchdog current: {}, voltage_low: {}, voltage_high: {}",
+                cmd.current, cmd.voltage_low, cmd.voltage_high
+            );
+
+            update_settings(|s| {
+                s.awd.current = cmd.current;
+                s.awd.voltage_low = cmd.voltage_low;
+                s.awd.voltage_high = cmd.voltage_high;
+            })
+            .await?;
+        }
+        Commands::Statistics => {
+            let cmd: QStatistics = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            if !(0..stats::NUM_VALUES as i32).contains(&cmd.index) {
+                return Err(Errors::InvalidParameter);
+            }
+            let stats = STATISTICS.lock().await;
+            let summary = stats.summary(cmd.index as usize);
+            let window = stats.window();
+            drop(stats);
+
+            let qstats = QStatistics {
+                index: cmd.index,
+                average: summary.average,
+                min: summary.min,
+                max: summary.max,
+                rms: summary.rms,
+                count: summary.count.min(i32::MAX as u32) as i32,
+                window: window as i32,
+            };
+
+            info!(
+                "sending statistics - index: {}, average: {}, min: {}, max: {}, rms: {}, count: {}, window: {}",
+                qstats.index, qstats.average, qstats.min, qstats.max, qstats.rms, qstats.count, qstats.window
+            );
+
+            response_len = serialize_response(&qstats, &mut response_data)?;
+        }
+        Commands::ResetStatistics => {
+            info!("receiving reset statistics");
+
+            STATISTICS.lock().await.reset();
+        }
+        Commands::SetStatisticsWindow => {
+            let cmd: QStatisticsWindow = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!("receiving statistics window: {}", cmd.window);
+
+            if cmd.window < 0 || !STATISTICS.lock().await.set_window(cmd.window as usize) {
+                return Err(Errors::InvalidParameter);
+            }
+        }
+        Commands::Capture => {
+            let cmd: QCapture = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            info!(
+                "receiving capture - source: {}, edge: {}, level: {}, pre_trigger: {}, decimation: {}",
+                cmd.source, cmd.edge, cmd.level, cmd.pre_trigger, cmd.decimation
+            );
+
+            let source = TriggerSource::from_i32(cmd.source).ok_or(Errors::InvalidParameter)?;
+            let edge = match cmd.edge {
+                0 => Edge::Rising,
+                1 => Edge::Falling,
+                _ => return Err(Errors::InvalidParameter),
+            };
+            if cmd.pre_trigger < 0 || cmd.decimation < 1 {
+                return Err(Errors::InvalidParameter);
+            }
+            let config = CaptureConfig {
+                source,
+                edge,
+                level: cmd.level,
+                pre_trigger: cmd.pre_trigger as usize,
+                decimation: cmd.decimation as u32,
+            };
+            if !config.is_valid() {
+                return Err(Errors::InvalidParameter);
+            }
+
+            CAPTURE.lock().await.arm(config);
+        }
+        Commands::CaptureTrigger => {
+            info!("receiving capture trigger");
+
+            let mut capture = CAPTURE.lock().await;
+            if capture.state != CaptureState::Armed {
+                return Err(Errors::InvalidMode);
+            }
+            capture.force_trigger();
+        }
+        Commands::CaptureStatus => {
+            let capture = CAPTURE.lock().await;
+            let config = capture.config();
+            let qstatus = QCaptureStatus {
+                state: capture.state as i32,
+                count: capture.count() as i32,
+                pre_trigger: config.pre_trigger as i32,
+                decimation: config.decimation as i32,
+            };
+            drop(capture);
+
+            info!(
+                "sending capture status - state: {}, count: {}, pre_trigger: {}, decimation: {}",
+                qstatus.state, qstatus.count, qstatus.pre_trigger, qstatus.decimation
+            );
+
+            response_len = serialize_response(&qstatus, &mut response_data)?;
+        }
+        Commands::CaptureData => {
+            let cmd: QCaptureData = quick_protobuf::deserialize_from_slice(&request.data)
+                .map_err(|_| Errors::ErrorDeserializingRequestData)?;
+
+            let capture = CAPTURE.lock().await;
+            if capture.state != CaptureState::Done {
+                return Err(Errors::InvalidMode);
+            }
+            if cmd.offset < 0 || cmd.offset as usize >= capture.count() {
+                return Err(Errors::InvalidParameter);
+            }
+
+            // little endian voltage and current of each point, as many as fit into one response
+            let mut data = Vec::with_capacity(CAPTURE_CHUNK * 8);
+            for index in
+                cmd.offset as usize..(cmd.offset as usize + CAPTURE_CHUNK).min(capture.count())
+            {
+                if let Some(point) = capture.point(index) {
+                    data.extend_from_slice(&point.voltage.to_le_bytes());
+                    data.extend_from_slice(&point.current.to_le_bytes());
+                }
+            }
+            drop(capture);
+
+            let qdata = QCaptureData {
+                offset: cmd.offset,
+                data: Cow::Owned(data),
+            };
+
+            info!(
+                "sending capture data - offset: {}, bytes: {}",
+                qdata.offset,
+                qdata.data.len()
+            );
+
+            response_len = serialize_response(&qdata, &mut response_data)?;
+        }
+        Commands::CaptureAbort => {
+            info!("receiving capture abort");
+
+            CAPTURE.lock().await.abort();
+        }
+        Commands::McuCalibration => {
+            let factory = FactoryCalibration::read();
+            let state = LOAD_STATE.lock().await;
+            let vdda = factory.vdda(state.cal, SCAN_FULL_SCALE);
+            let mcu_temp = mcu_temp(&state).unwrap_or(0);
+            let board_temp = temp_millicelsius(state.temp);
+            drop(state);
+
+            let qcal = QMcuCalibration {
+                vrefint_cal: factory.vrefint_cal as i32,
+                ts_cal1: factory.ts_cal1 as i32,
+                ts_cal2: factory.ts_cal2 as i32,
+                valid: factory.is_valid() as i32,
+                vrefint: control::vrefint(),
+                vdda,
+                mcu_temp,
+                board_temp,
+            };
+
+            info!(
+                "sending mcu calibration - vrefint_cal: {}, ts_cal1: {}, ts_cal2: {}, valid: {}, vrefint: {}, vdda: {}, mcu_temp: {}, board_temp: {}",
+                qcal.vrefint_cal, qcal.ts_cal1, qcal.ts_cal2, qcal.valid, qcal.vrefint, qcal.vdda, qcal.mcu_temp, qcal.board_temp
+            );
+
+            response_len = serialize_response(&qcal, &mut response_data)?;
+        }
+    };
+
+    response.id = request.id;
+    response.error = error;
+    response.data = Cow::Owned(response_data[..response_len].to_vec());
+    debug!(
+        "response.id: {}, response.error:{}, response.data: {:?}",
+        response.id,
+        response.error,
+        response_data[..response_len]
+    );
+    Ok(response_len)
+}
+
+// something to send to the host without a request
+enum Push {
+    State,
+    Event(Event),
+}
+
+// waits for the next state push or event the host subscribed to
+async fn next_push(next_state: Instant) -> Push {
+    let subscription = *SUBSCRIPTION.lock().await;
+    let state = async {
+        if subscription.interval == 0 {
+            pending::<()>().await;
+        }
+        Timer::at(next_state).await;
+        Push::State
+    };
+    let event = async {
+        if !subscription.events {
+            pending::<()>().await;
+        }
+        Push::Event(EVENTS.receive().await)
+    };
+    pin_mut!(state);
+    pin_mut!(event);
+    match select(state, event).await {
+        Either::Left((push, _)) | Either::Right((push, _)) => push,
+    }
+}
+
+// sends a message as one frame, split into as many packets as needed
+async fn write_frame<'d, T: Instance + 'd>(
+    class: &mut CdcAcmClass<'d, Driver<'d, T>>,
+    response: &QResponse<'_>,
+) -> Result<(), Disconnected> {
+    let mut response_bytes = [0u8; framing::MAX_MESSAGE];
+    let mut frame = [0u8; framing::MAX_FRAME];
+
+    // the frame carries the length, the response is written without a length prefix
+    let mut response_len = response.get_size();
+    let mut writer = Writer::new(BytesWriter::new(&mut response_bytes));
+    if response.write_message(&mut writer).is_err() {
+        error!("{}", Errors::to_string(&Errors::ErrorSerializingResponse));
+        // the host gets the error instead of waiting for its timeout, without data it fits
+        let error = QResponse {
+            id: response.id,
+            error: Errors::ErrorSerializingResponse as i32,
+            data: Cow::Borrowed(&[]),
+            kind: response.kind,
+        };
+        response_len = error.get_size();
+        let mut writer = Writer::new(BytesWriter::new(&mut response_bytes));
+        let _ = error.write_message(&mut writer);
+    }
+
+    let frame_len = framing::encode(&response_bytes[..response_len], &mut frame);
+    for chunk in frame[..frame_len].chunks(64) {
+        class.write_packet(chunk).await?;
+    }
+    Ok(())
+}
+
+async fn json_rpc<'d, T: Instance + 'd>(
+    class: &mut CdcAcmClass<'d, Driver<'d, T>>,
+) -> Result<(), Disconnected> {
+    let mut packet = [0u8; 64];
+    let mut request_bytes = [0u8; framing::MAX_MESSAGE + 4];
+    let mut decoder = Decoder::default();
+    let mut next_state = Instant::now();
+
+    // a new host has to subscribe again
+    *SUBSCRIPTION.lock().await = Subscription {
+        interval: 0,
+        events: false,
+    };
+
+    loop {
+        // the futures borrow the class, they are dropped before anything is written
+        let received = {
+            let read = class.read_packet(&mut packet);
+            let push = next_push(next_state);
+            pin_mut!(read);
+            pin_mut!(push);
+            match select(read, push).await {
+                Either::Left((Err(EndpointError::BufferOverflow), _)) => {
+                    warn!("packet too long, dropping the frame");
+                    decoder.discard();
+                    continue;
+                }
+                Either::Left((n, _)) => Either::Left(n?),
+                Either::Right((push, _)) => Either::Right(push),
+            }
+        };
+
+        let n = match received {
+            Either::Left(n) => n,
+            Either::Right(push) => {
+                let mut data = [0u8; RESPONSE_DATA_LEN];
+                let (kind, len) = match push {
+                    Push::State => {
+                        // keep the rate, but don't catch up after a late push
+                        let interval = SUBSCRIPTION.lock().await.interval;
+                        next_state = (next_state + Duration::from_millis(interval as u64))
+                            .max(Instant::now());
+                        (
+                            MSG_STATE,
+                            serialize_response(&load_state().await, &mut data),
+                        )
+                    }
+                    Push::Event(event) => {
+                        let qevent = QEvent {
+                            event: event.event,
+                            value: event.value,
+                            time: event.time as i32,
+                        };
+                        info!(
+                            "sending event - event: {}, value: {}, time: {}",
+                            qevent.event, qevent.value, qevent.time
+                        );
+                        (MSG_EVENT, serialize_response(&qevent, &mut data))
+                    }
+                };
+                if let Ok(len) = len {
+                    let message = QResponse {
+                        id: 0,
+                        error: 0,
+                        data: Cow::Borrowed(&data[..len]),
+                        kind,
+                    };
+                    write_frame(class, &message).await?;
+                }
+                continue;
+            }
+        };
+
+        // a packet can hold the end of one and the start of the next request
+        for &byte in &packet[..n] {
+            let mut response = QResponse::default();
+
+            match decoder.push(byte, &mut request_bytes) {
+                None => continue,
+                Some(Err(_)) => {
+                    error!("{}", Errors::to_string(&Errors::InvalidFrame));
+                    response.error = Errors::InvalidFrame as i32;
+                }
+                Some(Ok(len)) => {
+                    let bytes = &request_bytes[..len];
+                    match QRequest::from_reader(&mut BytesReader::from_bytes(bytes), bytes) {
+                        Ok(request) => {
+                            HEARTBEAT.signal(());
+                            if let Err(e) = process_request(&request, &mut response).await {
+                                error!("{}", Errors::to_string(&e));
+                                response = QResponse::default();
+                                response.id = request.id;
+                                response.error = e as i32;
+                            }
+                        }
+                        Err(_) => {
+                            error!("{}", Errors::to_string(&Errors::ErrorDeserializingRequest));
+                            response.error = Errors::ErrorDeserializingRequest as i32;
+                        }
+                    }
+                }
+            }
+
+            write_frame(class, &response).await?;
+        }
+    }
+}
+
+// mode of an SCPI setpoint, checked like the protobuf requests
+async fn scpi_mode(function: Function, value: i32) -> Result<Mode, ScpiError> {
+    let max_current: i32 = SETTINGS.lock().await.max_current.iter().sum();
+    match function {
+        Function::Current if value <= max_current => {
+            Ok(Mode::Current(control::split_current(value)))
+        }
+        Function::Voltage if value > 0 => Ok(Mode::Voltage(CvController::new(
+            value,
+            max_current,
+            CvController::DEFAULT_KP,
+            CvController::DEFAULT_KI,
+        ))),
+        Function::Resistance if value > 0 => Ok(Mode::Resistance(value)),
+        Function::Power => Ok(Mode::Power(value)),
+        _ => Err(ScpiError::DataOutOfRange),
+    }
+}
+
+// runs one SCPI command on the same control and state paths as the protobuf requests,
+// `setpoint` is the function and value that `INP ON` switches on with
+async fn scpi_execute(
+    command: Command,
+    setpoint: &mut (Function, i32),
+    errors: &mut ErrorQueue,
+) -> Result<heapless::String<SCPI_REPLY_LEN>, ScpiError> {
+    let mut reply = heapless::String::new();
+
+    match command {
+        Command::Identify => {
+            core::write!(
+                reply,
+                "{},{},{},{}-{}",
+                MANUFACTURER,
+                PRODUCT,
+                embassy_stm32::uid::uid_hex(),
+                env!("CARGO_PKG_VERSION"),
+                env!("GIT_HASH")
+            )
+            .map_err(|_| ScpiError::TooMuchData)?;
+        }
+        Command::Reset => {
+            *setpoint = (Function::Current, 0);
+            let control = LoadControl {
+                sdn: Some(1),
+                mode: Mode::Current([0; settings::NUM_CHANNELS]),
+            };
+            LOAD_CONTROL.send(control).await;
+        }
+        Command::OperationComplete => {
+            // commands are executed in order, everything before is done
+            let _ = reply.push('1');
+        }
+        Command::Clear => errors.clear(),
+        Command::NextError => {
+            let (code, message) = match errors.pop() {
+                Some(error) => (error.code(), error.message()),
+                None => (0, "No error"),
+            };
+            core::write!(reply, "{},\"{}\"", code, message).map_err(|_| ScpiError::TooMuchData)?;
+        }
+        Command::Set(function, value) => {
+            let control = LoadControl {
+                sdn: None,
+                mode: scpi_mode(function, value).await?,
+            };
+            LOAD_CONTROL.send(control).await;
+            *setpoint = (function, value);
+        }
+        Command::Input(on) => {
+            let sdn = if on { 0 } else { 1 };
+            check_switch_on(sdn)
+                .await
+                .map_err(|_| ScpiError::SettingsConflict)?;
+            let control = LoadControl {
+                sdn: Some(sdn),
+                mode: scpi_mode(setpoint.0, setpoint.1).await?,
+            };
+            LOAD_CONTROL.send(control).await;
+        }
+        Command::InputQuery => {
+            let on = LOAD_STATE.lock().await.sdn == 0;
+            let _ = reply.push(if on { '1' } else { '0' });
+        }
+        Command::Measure(quantity) => {
+            let state = load_state().await;
+            let value = match quantity {
+                Quantity::Current => {
+                    state.current0 + state.current1 + state.current2 + state.current3
+                }
+                Quantity::Voltage => state.voltage,
+                Quantity::Power => state.power,
+                Quantity::Temperature => state.temp,
+            };
+            core::write!(reply, "{}", Milli(value)).map_err(|_| ScpiError::TooMuchData)?;
+        }
+    }
+    Ok(reply)
+}
+
+// runs the commands of one line, the answers to its queries are joined into `output`
+async fn scpi_line(
+    line: &[u8],
+    setpoint: &mut (Function, i32),
+    errors: &mut ErrorQueue,
+    output: &mut heapless::Vec<u8, { SCPI_LINE_LEN + 1 }>,
+) {
+    let text = match core::str::from_utf8(line) {
+        Ok(text) => text,
+        Err(_) => return errors.push(ScpiError::Syntax),
+    };
+    info!("receiving scpi - {}", text);
+
+    for command in text.split(';') {
+        let result = match scpi::parse(command) {
+            Ok(Some(command)) => scpi_execute(command, setpoint, errors).await,
+            Ok(None) => continue,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(reply) if reply.is_empty() => {}
+            Ok(reply) => {
+                let separator: &[u8] = if output.is_empty() { b"" } else { b";" };
+                if output.len() + separator.len() + reply.len() > SCPI_LINE_LEN {
+                    errors.push(ScpiError::TooMuchData);
+                    continue;
+                }
+                let _ = output.extend_from_slice(separator);
+                let _ = output.extend_from_slice(reply.as_bytes());
+            }
+            Err(e) => {
+                warn!("scpi error {}: {}", e.code(), e.message());
+                errors.push(e);
+            }
+        }
+    }
+}
+
+async fn scpi<'d, T: Instance + 'd>(
+    class: &mut CdcAcmClass<'d, Driver<'d, T>>,
+) -> Result<(), Disconnected> {
+    let mut packet = [0u8; 64];
+    let mut line = heapless::Vec::<u8, SCPI_LINE_LEN>::new();
+    let mut overrun = false;
+    let mut errors = ErrorQueue::default();
+    let mut setpoint = (Function::Current, 0);
+
+    loop {
+        let n = match class.read_packet(&mut packet).await {
+            Ok(n) => n,
+            Err(EndpointError::BufferOverflow) => {
+                // the line is dropped with an input overrun at its terminator
+                overrun = true;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        for &byte in &packet[..n] {
+            if byte != b'\n' && byte != b'\r' {
+                overrun |= line.push(byte).is_err();
+                continue;
+            }
+            if core::mem::replace(&mut overrun, false) {
+                errors.push(ScpiError::InputOverrun);
+                line.clear();
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            HEARTBEAT.signal(());
+
+            // the answers to the queries of one line go into one line
+            let mut output = heapless::Vec::<u8, { SCPI_LINE_LEN + 1 }>::new();
+            scpi_line(&line, &mut setpoint, &mut errors, &mut output).await;
+            line.clear();
+
+            if !output.is_empty() {
+                // the answers leave room for the terminator
+                let _ = output.push(b'\n');
+                for chunk in output.chunks(64) {
+                    class.write_packet(chunk).await?;
+                }
+            }
+        }
+    }
+}
+
+// runs the SCPI commands sent in USBTMC messages, a message is one line and the answer to its
+// queries is sent when the host asks for it
+async fn usbtmc<'d, T: Instance + 'd>(
+    class: &mut UsbTmcClass<'d, Driver<'d, T>>,
+) -> Result<(), Disconnected> {
+    let mut data = [0u8; SCPI_LINE_LEN];
+    let mut line = heapless::Vec::<u8, SCPI_LINE_LEN>::new();
+    let mut overrun = false;
+    let mut output = heapless::Vec::<u8, { SCPI_LINE_LEN + 1 }>::new();
+    // bytes of the output the host has read already
+    let mut sent = 0;
+    let mut errors = ErrorQueue::default();
+    let mut setpoint = (Function::Current, 0);
+
+    loop {
+        let mut status_byte = 0;
+        if !errors.is_empty() {
+            status_byte |= STB_EAV;
+        }
+        if sent < output.len() {
+            status_byte |= STB_MAV;
+        }
+        class.set_status_byte(status_byte);
+
+        let message = match class.read_message(&mut data).await {
+            Ok(message) => message,
+            Err(EndpointError::BufferOverflow) => {
+                // the class dropped the whole message
+                errors.push(ScpiError::InputOverrun);
+                line.clear();
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        match message {
+            Message::Data { len, eom } => {
+                HEARTBEAT.signal(());
+                overrun |= line.extend_from_slice(&data[..len]).is_err();
+                if !eom {
+                    continue;
+                }
+                if core::mem::replace(&mut overrun, false) {
+                    errors.push(ScpiError::InputOverrun);
+                    line.clear();
+                    continue;
+                }
+
+                if sent < output.len() {
+                    errors.push(ScpiError::QueryInterrupted);
+                }
+                output.clear();
+                sent = 0;
+                scpi_line(&line, &mut setpoint, &mut errors, &mut output).await;
+                line.clear();
+                if !output.is_empty() {
+                    let _ = output.push(b'\n');
+                }
+            }
+            Message::ReadRequest(request) => {
+                HEARTBEAT.signal(());
+                // an empty answer with EOM keeps the host from waiting for its timeout
+                if sent == output.len() {
+                    errors.push(ScpiError::QueryUnterminated);
+                }
+                let len = (output.len() - sent).min(request.max_len());
+                let n = class.write_message(request, &output[sent..], true).await?;
+                sent += n;
+                // the rest of an aborted answer is dropped
+                if sent == output.len() || n < len {
+                    output.clear();
+                    sent = 0;
+                }
+            }
+            Message::Clear => {
+                info!("USBTMC device clear");
+                line.clear();
+                overrun = false;
+                output.clear();
+                sent = 0;
+            }
+        }
     }
 }
@@ -0,0 +1,82 @@
+//! Events pushed to a subscribed host.
+//!
+//! The control task compares the load state with the one of its previous step, so every
+//! mode change, list step and protection trip is reported, even if it only lasts for one
+//! step and a polling host would have missed it.
+
+/// latched FAULT_* bits, also sent when they are cleared
+pub const EVENT_FAULT: i32 = 1;
+/// mode id
+pub const EVENT_MODE: i32 = 2;
+/// index of the list step, also sent when the next loop starts
+pub const EVENT_LIST_STEP: i32 = 3;
+/// list state
+pub const EVENT_LIST_STATE: i32 = 4;
+/// over-temperature state
+pub const EVENT_THERMAL: i32 = 5;
+/// SOA limiter state
+pub const EVENT_SOA: i32 = 6;
+/// 1 if the load was shut down, 0 if it was switched on
+pub const EVENT_SHUTDOWN: i32 = 7;
+
+/// The parts of the load state that events are sent for.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    pub fault: i32,
+    pub mode: i32,
+    pub list_state: i32,
+    pub list_step: i32,
+    pub list_loop: i32,
+    pub thermal: i32,
+    pub soa: i32,
+    pub sdn: i32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Event {
+    pub event: i32,
+    pub value: i32,
+    /// ms since boot
+    pub time: u32,
+}
+
+pub struct EventDetector {
+    last: Option<Snapshot>,
+}
+
+impl EventDetector {
+    pub const fn default() -> Self {
+        EventDetector { last: None }
+    }
+
+    /// Calls `post` with every change since the last snapshot, the first snapshot only sets
+    /// the reference.
+    pub fn update(&mut self, snapshot: Snapshot, mut post: impl FnMut(i32, i32)) {
+        let last = match self.last.replace(snapshot) {
+            Some(last) if last != snapshot => last,
+            _ => return,
+        };
+
+        if snapshot.fault != last.fault {
+            post(EVENT_FAULT, snapshot.fault);
+        }
+        if snapshot.sdn != last.sdn {
+            post(EVENT_SHUTDOWN, snapshot.sdn);
+        }
+        if snapshot.mode != last.mode {
+            post(EVENT_MODE, snapshot.mode);
+        }
+        if snapshot.list_state != last.list_state {
+            post(EVENT_LIST_STATE, snapshot.list_state);
+        }
+        if (snapshot.list_step, snapshot.list_loop) != (last.list_step, last.list_loop) {
+            post(EVENT_LIST_STEP, snapshot.list_step);
+        }
+        if snapshot.thermal != last.thermal {
+            post(EVENT_THERMAL, snapshot.thermal);
+        }
+        if snapshot.soa != last.soa {
+            post(EVENT_SOA, snapshot.soa);
+        }
+    }
+}
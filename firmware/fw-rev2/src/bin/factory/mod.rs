@@ -0,0 +1,75 @@
+//! Factory calibration of the ADC in the system memory of the STM32L0.
+//!
+//! VREFINT_CAL and the two temperature sensor points TS_CAL1 (30°C) and TS_CAL2 (130°C) are
+//! raw 12-bit conversions taken by ST at VDDA = 3.0 V. The readings they are compared with
+//! only have to share one scale, e.g. the 15-bit results of the oversampled scan.
+
+const VREFINT_CAL_ADDR: usize = 0x1ff8_0078;
+const TS_CAL1_ADDR: usize = 0x1ff8_007a;
+const TS_CAL2_ADDR: usize = 0x1ff8_007e;
+
+// conditions of the factory measurements
+const CAL_VDDA_MV: i64 = 3000;
+const CAL_FULL_SCALE: i64 = 4095;
+const TS_CAL1_TEMP: i64 = 30_000;
+const TS_CAL2_TEMP: i64 = 130_000;
+
+// nominal internal reference in µV, used if the calibration values are missing
+pub const DEFAULT_VREFINT: i32 = 1_224_000;
+
+#[derive(Clone, Copy)]
+pub struct FactoryCalibration {
+    pub vrefint_cal: u16,
+    pub ts_cal1: u16,
+    pub ts_cal2: u16,
+}
+
+impl FactoryCalibration {
+    /// Reads the values from system memory.
+    pub fn read() -> Self {
+        // SAFETY: the addresses are in the always readable system memory of the STM32L0
+        unsafe {
+            FactoryCalibration {
+                vrefint_cal: core::ptr::read_volatile(VREFINT_CAL_ADDR as *const u16),
+                ts_cal1: core::ptr::read_volatile(TS_CAL1_ADDR as *const u16),
+                ts_cal2: core::ptr::read_volatile(TS_CAL2_ADDR as *const u16),
+            }
+        }
+    }
+
+    /// Erased or zero values would give nonsense, the sensor slope has to be positive.
+    pub fn is_valid(&self) -> bool {
+        let valid = |value: u16| value > 0 && value < 0xfff;
+        valid(self.vrefint_cal)
+            && valid(self.ts_cal1)
+            && valid(self.ts_cal2)
+            && self.ts_cal2 > self.ts_cal1
+    }
+
+    /// Internal reference voltage in µV
+    pub fn vrefint(&self) -> i32 {
+        (CAL_VDDA_MV * 1000 * self.vrefint_cal as i64 / CAL_FULL_SCALE) as i32
+    }
+
+    /// Analog supply in mV from a VREFINT reading with `full_scale` as the largest code.
+    pub fn vdda(&self, vref: i32, full_scale: i32) -> i32 {
+        if vref <= 0 {
+            return 0;
+        }
+        (CAL_VDDA_MV * self.vrefint_cal as i64 * full_scale as i64 / (CAL_FULL_SCALE * vref as i64))
+            as i32
+    }
+
+    /// Temperature of the MCU in m°C from a temperature sensor and a VREFINT reading of the
+    /// same scan.
+    pub fn temperature(&self, ts: i32, vref: i32) -> i32 {
+        if vref <= 0 || self.ts_cal2 <= self.ts_cal1 {
+            return 0;
+        }
+        // the sensor reading scaled to VDDA = 3.0 V like the calibration points
+        let ts = ts as i64 * self.vrefint_cal as i64;
+        let cal1 = self.ts_cal1 as i64 * vref as i64;
+        let slope = (self.ts_cal2 - self.ts_cal1) as i64 * vref as i64;
+        (TS_CAL1_TEMP + (ts - cal1) * (TS_CAL2_TEMP - TS_CAL1_TEMP) / slope) as i32
+    }
+}
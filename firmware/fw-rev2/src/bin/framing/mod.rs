@@ -0,0 +1,139 @@
+//! Framing of the messages on the CDC-ACM byte stream.
+//!
+//! Every message is followed by its CRC-32 (little endian), COBS encoded and terminated with
+//! a zero byte. The encoded frame contains no other zero bytes, so a frame can span any number
+//! of USB packets and a receiver that lost track or got garbage resynchronizes at the next zero.
+
+use crate::settings::crc32;
+
+/// largest message without the CRC
+pub const MAX_MESSAGE: usize = 256;
+
+const CRC_LEN: usize = 4;
+
+/// largest frame including the delimiter, COBS adds one byte per 254 bytes plus one
+pub const MAX_FRAME: usize = MAX_MESSAGE + CRC_LEN + (MAX_MESSAGE + CRC_LEN) / 254 + 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FrameError {
+    /// more than MAX_FRAME bytes without a delimiter
+    TooLong,
+    /// broken COBS encoding or shorter than the CRC
+    Invalid,
+    Crc,
+}
+
+/// Encodes `message` into `frame`, which has to hold MAX_FRAME bytes, and returns the length
+/// of the frame including the delimiter.
+pub fn encode(message: &[u8], frame: &mut [u8]) -> usize {
+    let crc = crc32(message).to_le_bytes();
+
+    // position of the code byte of the current block
+    let mut code_pos = 0;
+    let mut code = 1u8;
+    let mut len = 1;
+    for &byte in message.iter().chain(crc.iter()) {
+        if byte != 0 {
+            frame[len] = byte;
+            len += 1;
+            code += 1;
+        }
+        if byte == 0 || code == 0xff {
+            frame[code_pos] = code;
+            code_pos = len;
+            len += 1;
+            code = 1;
+        }
+    }
+    frame[code_pos] = code;
+    frame[len] = 0;
+    len + 1
+}
+
+// decodes a frame without the delimiter, returns the length of the data
+fn cobs_decode(frame: &[u8], data: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    let mut pos = 0;
+    while pos < frame.len() {
+        let code = frame[pos] as usize;
+        let end = pos + code;
+        if code == 0 || end > frame.len() || len + code - 1 > data.len() {
+            return None;
+        }
+        data[len..len + code - 1].copy_from_slice(&frame[pos + 1..end]);
+        len += code - 1;
+        pos = end;
+        // a block shorter than 254 bytes ends with a zero, except for the last one
+        if code < 0xff && pos < frame.len() {
+            if len == data.len() {
+                return None;
+            }
+            data[len] = 0;
+            len += 1;
+        }
+    }
+    Some(len)
+}
+
+/// Collects the received bytes until a frame is complete.
+pub struct Decoder {
+    frame: [u8; MAX_FRAME],
+    len: usize,
+    overflow: bool,
+}
+
+impl Decoder {
+    pub const fn default() -> Self {
+        Decoder {
+            frame: [0; MAX_FRAME],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Adds a received byte. At the end of a frame the message is decoded into `message`,
+    /// which has to hold MAX_MESSAGE + 4 bytes, and its length is returned. Empty frames
+    /// are ignored, so a host can send a zero to resynchronize.
+    pub fn push(&mut self, byte: u8, message: &mut [u8]) -> Option<Result<usize, FrameError>> {
+        if byte != 0 {
+            if self.len < self.frame.len() {
+                self.frame[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflow = true;
+            }
+            return None;
+        }
+
+        let len = core::mem::replace(&mut self.len, 0);
+        if core::mem::replace(&mut self.overflow, false) {
+            return Some(Err(FrameError::TooLong));
+        }
+        if len == 0 {
+            return None;
+        }
+
+        let len = match cobs_decode(&self.frame[..len], message) {
+            Some(len) if len >= CRC_LEN => len - CRC_LEN,
+            _ => return Some(Err(FrameError::Invalid)),
+        };
+        let crc = u32::from_le_bytes([
+            message[len],
+            message[len + 1],
+            message[len + 2],
+            message[len + 3],
+        ]);
+        if crc != crc32(&message[..len]) {
+            return Some(Err(FrameError::Crc));
+        }
+        Some(Ok(len))
+    }
+
+    /// Drops the frame being received, e.g. after a lost packet. It ends with
+    /// `FrameError::TooLong` at the next zero, so the host gets an error and both sides
+    /// resynchronize there.
+    pub fn discard(&mut self) {
+        self.len = 0;
+        self.overflow = true;
+    }
+}
@@ -0,0 +1,202 @@
+//! List mode that runs a table of load steps autonomously.
+//!
+//! Every step selects a mode and a setpoint that is held for the dwell time. An optional
+//! slew ramps the setpoint from the previous step of the same mode, the ramp is part of
+//! the dwell time. All times are in µs of the firmware clock, so the host is not involved
+//! in the timing at all.
+
+pub const MAX_STEPS: usize = 32;
+
+// setpoint updates while ramping
+const RAMP_INTERVAL_US: u64 = 1_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StepMode {
+    // total current in mA
+    Current,
+    // voltage in mV
+    Voltage,
+    // resistance in mΩ
+    Resistance,
+    // power in mW
+    Power,
+}
+
+impl StepMode {
+    pub fn from_i32(value: i32) -> Option<StepMode> {
+        match value {
+            0 => Some(StepMode::Current),
+            1 => Some(StepMode::Voltage),
+            2 => Some(StepMode::Resistance),
+            3 => Some(StepMode::Power),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Step {
+    pub mode: StepMode,
+    pub setpoint: i32,
+    /// dwell time in ms
+    pub dwell: i32,
+    /// slew rate in setpoint units per second, 0 switches immediately
+    pub slew: i32,
+}
+
+impl Step {
+    pub const fn default() -> Self {
+        Step {
+            mode: StepMode::Current,
+            setpoint: 0,
+            dwell: 0,
+            slew: 0,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let setpoint_valid = match self.mode {
+            StepMode::Resistance => self.setpoint > 0,
+            _ => self.setpoint >= 0,
+        };
+        setpoint_valid && self.dwell > 0 && self.slew >= 0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ListState {
+    Idle = 0,
+    Running = 1,
+    Paused = 2,
+    Done = 3,
+    Aborted = 4,
+}
+
+/// Setpoint the list requests at a given time.
+pub struct Target {
+    pub mode: StepMode,
+    pub setpoint: i32,
+    /// the step changed since the last poll
+    pub new_step: bool,
+    /// time of the next poll in µs, `None` while paused
+    pub deadline: Option<u64>,
+}
+
+pub struct ListRunner {
+    count: usize,
+    // 0 repeats forever
+    loops: i32,
+    pub index: usize,
+    pub loop_count: i32,
+    pub state: ListState,
+    step_start: u64,
+    paused_at: u64,
+    // setpoint the ramp of the current step starts from
+    from: i32,
+    started: bool,
+}
+
+impl ListRunner {
+    pub fn new(count: usize, loops: i32, now: u64) -> Self {
+        ListRunner {
+            count,
+            loops,
+            index: 0,
+            loop_count: 0,
+            state: ListState::Running,
+            step_start: now,
+            paused_at: now,
+            from: 0,
+            started: false,
+        }
+    }
+
+    pub fn pause(&mut self, now: u64) {
+        if self.state == ListState::Running {
+            self.state = ListState::Paused;
+            self.paused_at = now;
+        }
+    }
+
+    pub fn resume(&mut self, now: u64) {
+        if self.state == ListState::Paused {
+            self.state = ListState::Running;
+            // the paused time does not count as dwell time
+            self.step_start += now - self.paused_at;
+        }
+    }
+
+    /// Returns the setpoint for the time `now`, `None` when the list has finished.
+    pub fn poll(&mut self, steps: &[Step], now: u64) -> Option<Target> {
+        let mut new_step = !self.started;
+        self.started = true;
+
+        let now = if self.state == ListState::Paused {
+            self.paused_at
+        } else {
+            now
+        };
+
+        loop {
+            let step = &steps[self.index];
+            let end = self.step_start + step.dwell as u64 * 1000;
+            if now < end {
+                break;
+            }
+
+            // ramp the next step from where this one ended if it has the same mode
+            let prev = *step;
+            self.index += 1;
+            if self.index >= self.count {
+                self.index = 0;
+                self.loop_count += 1;
+                if self.loops != 0 && self.loop_count >= self.loops {
+                    self.state = ListState::Done;
+                    return None;
+                }
+            }
+            self.step_start = end;
+            self.from = if steps[self.index].mode == prev.mode {
+                prev.setpoint
+            } else {
+                0
+            };
+            new_step = true;
+        }
+
+        let step = &steps[self.index];
+        let end = self.step_start + step.dwell as u64 * 1000;
+        let (setpoint, ramping) = self.ramp(step, now - self.step_start);
+        let deadline = match self.state {
+            ListState::Paused => None,
+            _ if ramping => Some((now + RAMP_INTERVAL_US).min(end)),
+            _ => Some(end),
+        };
+
+        Some(Target {
+            mode: step.mode,
+            setpoint,
+            new_step,
+            deadline,
+        })
+    }
+
+    // setpoint `elapsed` µs after the start of the step and whether it is still ramping
+    fn ramp(&self, step: &Step, elapsed: u64) -> (i32, bool) {
+        // a resistance or voltage ramp from 0 would short the source, those
+        // only ramp between steps of the same mode
+        let can_ramp = self.from != 0 || matches!(step.mode, StepMode::Current | StepMode::Power);
+        if step.slew == 0 || !can_ramp {
+            return (step.setpoint, false);
+        }
+
+        let delta = (step.slew as u64 * elapsed / 1_000_000).min(i32::MAX as u64) as i32;
+        if step.setpoint > self.from {
+            let setpoint = self.from.saturating_add(delta);
+            (setpoint.min(step.setpoint), setpoint < step.setpoint)
+        } else {
+            let setpoint = self.from.saturating_sub(delta);
+            (setpoint.max(step.setpoint), setpoint > step.setpoint)
+        }
+    }
+}
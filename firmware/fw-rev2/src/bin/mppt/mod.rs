@@ -0,0 +1,257 @@
+//! Maximum power point tracking and I-V curve capture for solar panels.
+//!
+//! Both start with the load switched off to measure the open-circuit voltage. The tracker
+//! then holds the source in constant-voltage mode and moves the voltage setpoint with
+//! perturb and observe. The I-V curve is a voltage sweep from the open-circuit voltage
+//! down to a minimum voltage close to the short-circuit current.
+
+use crate::list::{StepMode, Target};
+use crate::sweep::{Sweep, SweepConfig, SweepResults, SweepState, MAX_POINTS};
+
+// first operating point in per mille of the open-circuit voltage
+const START_VOLTAGE: i32 = 800;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MpptState {
+    Idle = 0,
+    // measuring the open-circuit voltage
+    Starting = 1,
+    Tracking = 2,
+    Aborted = 3,
+}
+
+#[derive(Clone, Copy)]
+pub struct MpptConfig {
+    /// perturbation of the voltage setpoint in mV
+    pub step: i32,
+    /// time per perturbation in ms
+    pub interval: i32,
+}
+
+impl MpptConfig {
+    pub fn is_valid(&self) -> bool {
+        self.step > 0 && self.interval > 0
+    }
+}
+
+/// Averaged operating point of the last interval.
+pub struct MpptStatus {
+    pub state: MpptState,
+    /// voltage in mV
+    pub voltage: i32,
+    /// current in mA
+    pub current: i32,
+    /// power in mW
+    pub power: i32,
+    /// open-circuit voltage in mV
+    pub voc: i32,
+}
+
+impl MpptStatus {
+    pub const fn default() -> Self {
+        MpptStatus {
+            state: MpptState::Idle,
+            voltage: 0,
+            current: 0,
+            power: 0,
+            voc: 0,
+        }
+    }
+
+    pub fn starting() -> Self {
+        MpptStatus {
+            state: MpptState::Starting,
+            ..Self::default()
+        }
+    }
+}
+
+// averages the samples of the second half of an interval, so the source has settled
+struct Average {
+    start: u64,
+    length: u64,
+    voltage: i64,
+    current: i64,
+    samples: i64,
+}
+
+impl Average {
+    fn new(now: u64, length_ms: i32) -> Self {
+        Average {
+            start: now,
+            length: length_ms as u64 * 1000,
+            voltage: 0,
+            current: 0,
+            samples: 0,
+        }
+    }
+
+    fn add(&mut self, now: u64, voltage: i32, current: i32) {
+        if now >= self.start + self.length / 2 {
+            self.voltage += voltage as i64;
+            self.current += current as i64;
+            self.samples += 1;
+        }
+    }
+
+    fn end(&self) -> u64 {
+        self.start + self.length
+    }
+
+    // averaged voltage and current, restarts the interval
+    fn take(&mut self) -> (i32, i32) {
+        let samples = self.samples.max(1);
+        let result = (
+            (self.voltage / samples) as i32,
+            (self.current / samples) as i32,
+        );
+        *self = Average::new(self.end(), (self.length / 1000) as i32);
+        result
+    }
+}
+
+pub struct Mppt {
+    config: MpptConfig,
+    average: Average,
+    voc: Option<i32>,
+    setpoint: i32,
+    // +1 or -1
+    direction: i32,
+    last_power: i32,
+}
+
+impl Mppt {
+    pub fn new(config: MpptConfig, now: u64) -> Self {
+        Mppt {
+            config,
+            average: Average::new(now, config.interval),
+            voc: None,
+            setpoint: 0,
+            direction: 1,
+            last_power: 0,
+        }
+    }
+
+    pub fn add_sample(&mut self, now: u64, voltage: i32, current: i32) {
+        self.average.add(now, voltage, current);
+    }
+
+    pub fn poll(&mut self, now: u64, status: &mut MpptStatus) -> Target {
+        let mut new_step = false;
+        while now >= self.average.end() {
+            let (voltage, current) = self.average.take();
+            match self.voc {
+                None => {
+                    self.voc = Some(voltage);
+                    self.setpoint = voltage * START_VOLTAGE / 1000;
+                    status.voc = voltage;
+                    status.state = MpptState::Tracking;
+                    new_step = true;
+                }
+                Some(voc) => {
+                    let power = (voltage as i64 * current as i64 / 1000) as i32;
+                    // perturb and observe, turn around when the power went down
+                    if power < self.last_power {
+                        self.direction = -self.direction;
+                    }
+                    self.last_power = power;
+                    let step = self.config.step;
+                    self.setpoint =
+                        (self.setpoint + self.direction * step).clamp(step, voc.max(step));
+
+                    status.voltage = voltage;
+                    status.current = current;
+                    status.power = power;
+                }
+            }
+        }
+
+        let deadline = Some(self.average.end());
+        match self.voc {
+            None => Target {
+                mode: StepMode::Current,
+                setpoint: 0,
+                new_step,
+                deadline,
+            },
+            Some(_) => Target {
+                mode: StepMode::Voltage,
+                setpoint: self.setpoint,
+                new_step,
+                deadline,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct IvCurveConfig {
+    /// number of points including the open-circuit voltage
+    pub points: i32,
+    /// dwell time per point in ms
+    pub dwell: i32,
+    /// last point of the sweep in mV
+    pub min_voltage: i32,
+}
+
+impl IvCurveConfig {
+    pub fn is_valid(&self) -> bool {
+        (2..=MAX_POINTS as i32).contains(&self.points) && self.dwell > 0 && self.min_voltage >= 0
+    }
+}
+
+pub struct IvCurve {
+    config: IvCurveConfig,
+    average: Average,
+    sweep: Option<Sweep>,
+}
+
+impl IvCurve {
+    pub fn new(config: IvCurveConfig, now: u64) -> Self {
+        IvCurve {
+            config,
+            average: Average::new(now, config.dwell),
+            sweep: None,
+        }
+    }
+
+    pub fn add_sample(&mut self, now: u64, voltage: i32, current: i32) {
+        match &mut self.sweep {
+            Some(sweep) => sweep.add_sample(now, voltage, current),
+            None => self.average.add(now, voltage, current),
+        }
+    }
+
+    /// Returns the setpoint, `None` when the curve is complete.
+    pub fn poll(&mut self, now: u64, temp: i32, results: &mut SweepResults) -> Option<Target> {
+        if self.sweep.is_none() {
+            if now < self.average.end() {
+                return Some(Target {
+                    mode: StepMode::Current,
+                    setpoint: 0,
+                    new_step: false,
+                    deadline: Some(self.average.end()),
+                });
+            }
+
+            let (voc, _) = self.average.take();
+            if voc <= self.config.min_voltage {
+                // no source connected
+                results.state = SweepState::Done;
+                return None;
+            }
+            let config = SweepConfig {
+                mode: StepMode::Voltage,
+                start: voc,
+                stop: self.config.min_voltage,
+                points: self.config.points,
+                dwell: self.config.dwell,
+            };
+            self.sweep = Some(Sweep::new(config, now));
+        }
+
+        self.sweep
+            .as_mut()
+            .and_then(|sweep| sweep.poll(now, temp, results))
+    }
+}
@@ -0,0 +1,129 @@
+//! Over-current-protection trip test for power supplies.
+//!
+//! The load current is ramped in steps until the input voltage falls below an absolute
+//! threshold or drops by a percentage of the voltage measured at the start of the test.
+
+use crate::list::{StepMode, Target};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OcpState {
+    Idle = 0,
+    Running = 1,
+    Tripped = 2,
+    // the stop current was reached without a trip
+    NoTrip = 3,
+    Aborted = 4,
+}
+
+#[derive(Clone, Copy)]
+pub struct OcpConfig {
+    /// currents in mA
+    pub start: i32,
+    pub stop: i32,
+    pub step: i32,
+    /// dwell time per step in ms
+    pub dwell: i32,
+    /// trip voltage in mV, 0 disables the absolute threshold
+    pub threshold: i32,
+    /// trip at a voltage drop in percent of the start voltage, 0 disables it
+    pub drop: i32,
+}
+
+impl OcpConfig {
+    pub fn is_valid(&self) -> bool {
+        self.start >= 0
+            && self.stop >= self.start
+            && self.step > 0
+            && self.dwell > 0
+            && self.threshold >= 0
+            && (0..100).contains(&self.drop)
+            && (self.threshold > 0 || self.drop > 0)
+    }
+}
+
+pub struct OcpResult {
+    pub state: OcpState,
+    /// current setpoint at the trip in mA
+    pub current: i32,
+    /// last voltage before the trip in mV
+    pub voltage: i32,
+    /// time from the start of the test to the trip in µs
+    pub time: u64,
+}
+
+impl OcpResult {
+    pub const fn default() -> Self {
+        OcpResult {
+            state: OcpState::Idle,
+            current: 0,
+            voltage: 0,
+            time: 0,
+        }
+    }
+}
+
+pub struct OcpTest {
+    config: OcpConfig,
+    start: u64,
+    step_start: u64,
+    setpoint: i32,
+    started: bool,
+    // voltage at the start of the test in mV
+    reference: Option<i32>,
+    last_voltage: Option<i32>,
+}
+
+impl OcpTest {
+    pub fn new(config: OcpConfig, now: u64) -> Self {
+        OcpTest {
+            config,
+            start: now,
+            step_start: now,
+            setpoint: config.start,
+            started: false,
+            reference: None,
+            last_voltage: None,
+        }
+    }
+
+    /// Checks a voltage sample in mV, returns `true` when the source tripped.
+    pub fn check(&mut self, now: u64, voltage: i32, result: &mut OcpResult) -> bool {
+        let reference = *self.reference.get_or_insert(voltage);
+        let collapsed = voltage < self.config.threshold
+            || (self.config.drop > 0 && voltage < reference * (100 - self.config.drop) / 100);
+
+        if collapsed {
+            result.state = OcpState::Tripped;
+            result.current = self.setpoint;
+            result.voltage = self.last_voltage.unwrap_or(voltage);
+            result.time = now - self.start;
+            return true;
+        }
+        self.last_voltage = Some(voltage);
+        false
+    }
+
+    /// Returns the current setpoint, `None` when the stop current was held for the dwell
+    /// time without a trip.
+    pub fn poll(&mut self, now: u64, result: &mut OcpResult) -> Option<Target> {
+        let new_step = !self.started;
+        self.started = true;
+
+        let dwell = self.config.dwell as u64 * 1000;
+        while now >= self.step_start + dwell {
+            if self.setpoint >= self.config.stop {
+                result.state = OcpState::NoTrip;
+                return None;
+            }
+            self.setpoint = (self.setpoint + self.config.step).min(self.config.stop);
+            self.step_start += dwell;
+        }
+
+        Some(Target {
+            mode: StepMode::Current,
+            setpoint: self.setpoint,
+            new_step,
+            deadline: Some(self.step_start + dwell),
+        })
+    }
+}
@@ -61,6 +61,7 @@ pub struct QResponse<'a> {
     pub id: i32,
     pub error: i32,
     pub data: Cow<'a, [u8]>,
+    pub kind: i32,
 }
 
 impl<'a> MessageRead<'a> for QResponse<'a> {
@@ -71,6 +72,7 @@ impl<'a> MessageRead<'a> for QResponse<'a> {
                 Ok(8) => msg.id = r.read_int32(bytes)?,
                 Ok(16) => msg.error = r.read_int32(bytes)?,
                 Ok(26) => msg.data = r.read_bytes(bytes).map(Cow::Borrowed)?,
+                Ok(32) => msg.kind = r.read_int32(bytes)?,
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -85,12 +87,14 @@ impl<'a> MessageWrite for QResponse<'a> {
         + if self.id == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.id) as u64) }
         + if self.error == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.error) as u64) }
         + if self.data == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.data).len()) }
+        + if self.kind == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.kind) as u64) }
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
         if self.id != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.id))?; }
         if self.error != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.error))?; }
         if self.data != Cow::Borrowed(b"") { w.write_with_tag(26, |w| w.write_bytes(&**&self.data))?; }
+        if self.kind != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.kind))?; }
         Ok(())
     }
 }
@@ -104,6 +108,11 @@ pub struct QControl {
     pub dac1: i32,
     pub dac2: i32,
     pub dac3: i32,
+    pub current0: i32,
+    pub current1: i32,
+    pub current2: i32,
+    pub current3: i32,
+    pub raw: i32,
 }
 
 impl<'a> MessageRead<'a> for QControl {
@@ -117,6 +126,11 @@ impl<'a> MessageRead<'a> for QControl {
                 Ok(32) => msg.dac1 = r.read_int32(bytes)?,
                 Ok(40) => msg.dac2 = r.read_int32(bytes)?,
                 Ok(48) => msg.dac3 = r.read_int32(bytes)?,
+                Ok(56) => msg.current0 = r.read_int32(bytes)?,
+                Ok(64) => msg.current1 = r.read_int32(bytes)?,
+                Ok(72) => msg.current2 = r.read_int32(bytes)?,
+                Ok(80) => msg.current3 = r.read_int32(bytes)?,
+                Ok(88) => msg.raw = r.read_int32(bytes)?,
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -134,6 +148,11 @@ impl MessageWrite for QControl {
         + if self.dac1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dac1) as u64) }
         + if self.dac2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dac2) as u64) }
         + if self.dac3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dac3) as u64) }
+        + if self.current0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current0) as u64) }
+        + if self.current1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current1) as u64) }
+        + if self.current2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current2) as u64) }
+        + if self.current3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current3) as u64) }
+        + if self.raw == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.raw) as u64) }
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
@@ -143,6 +162,11 @@ impl MessageWrite for QControl {
         if self.dac1 != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.dac1))?; }
         if self.dac2 != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.dac2))?; }
         if self.dac3 != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.dac3))?; }
+        if self.current0 != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.current0))?; }
+        if self.current1 != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.current1))?; }
+        if self.current2 != 0i32 { w.write_with_tag(72, |w| w.write_int32(*&self.current2))?; }
+        if self.current3 != 0i32 { w.write_with_tag(80, |w| w.write_int32(*&self.current3))?; }
+        if self.raw != 0i32 { w.write_with_tag(88, |w| w.write_int32(*&self.raw))?; }
         Ok(())
     }
 }
@@ -150,6 +174,1582 @@ impl MessageWrite for QControl {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct QState {
+    pub current0: i32,
+    pub current1: i32,
+    pub current2: i32,
+    pub current3: i32,
+    pub voltage: i32,
+    pub power: i32,
+    pub temp: i32,
+    pub sdn: i32,
+    pub mode: i32,
+    pub list_state: i32,
+    pub list_step: i32,
+    pub list_loop: i32,
+    pub thermal: i32,
+    pub fault: i32,
+}
+
+impl<'a> MessageRead<'a> for QState {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.current0 = r.read_sint32(bytes)?,
+                Ok(16) => msg.current1 = r.read_sint32(bytes)?,
+                Ok(24) => msg.current2 = r.read_sint32(bytes)?,
+                Ok(32) => msg.current3 = r.read_sint32(bytes)?,
+                Ok(40) => msg.voltage = r.read_sint32(bytes)?,
+                Ok(48) => msg.power = r.read_sint32(bytes)?,
+                Ok(56) => msg.temp = r.read_sint32(bytes)?,
+                Ok(64) => msg.sdn = r.read_int32(bytes)?,
+                Ok(72) => msg.mode = r.read_int32(bytes)?,
+                Ok(80) => msg.list_state = r.read_int32(bytes)?,
+                Ok(88) => msg.list_step = r.read_int32(bytes)?,
+                Ok(96) => msg.list_loop = r.read_int32(bytes)?,
+                Ok(104) => msg.thermal = r.read_int32(bytes)?,
+                Ok(112) => msg.fault = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QState {
+    fn get_size(&self) -> usize {
+        0
+        + if self.current0 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.current0)) }
+        + if self.current1 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.current1)) }
+        + if self.current2 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.current2)) }
+        + if self.current3 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.current3)) }
+        + if self.voltage == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.voltage)) }
+        + if self.power == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.power)) }
+        + if self.temp == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.temp)) }
+        + if self.sdn == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.sdn) as u64) }
+        + if self.mode == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.mode) as u64) }
+        + if self.list_state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.list_state) as u64) }
+        + if self.list_step == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.list_step) as u64) }
+        + if self.list_loop == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.list_loop) as u64) }
+        + if self.thermal == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.thermal) as u64) }
+        + if self.fault == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.fault) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.current0 != 0i32 { w.write_with_tag(8, |w| w.write_sint32(*&self.current0))?; }
+        if self.current1 != 0i32 { w.write_with_tag(16, |w| w.write_sint32(*&self.current1))?; }
+        if self.current2 != 0i32 { w.write_with_tag(24, |w| w.write_sint32(*&self.current2))?; }
+        if self.current3 != 0i32 { w.write_with_tag(32, |w| w.write_sint32(*&self.current3))?; }
+        if self.voltage != 0i32 { w.write_with_tag(40, |w| w.write_sint32(*&self.voltage))?; }
+        if self.power != 0i32 { w.write_with_tag(48, |w| w.write_sint32(*&self.power))?; }
+        if self.temp != 0i32 { w.write_with_tag(56, |w| w.write_sint32(*&self.temp))?; }
+        if self.sdn != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.sdn))?; }
+        if self.mode != 0i32 { w.write_with_tag(72, |w| w.write_int32(*&self.mode))?; }
+        if self.list_state != 0i32 { w.write_with_tag(80, |w| w.write_int32(*&self.list_state))?; }
+        if self.list_step != 0i32 { w.write_with_tag(88, |w| w.write_int32(*&self.list_step))?; }
+        if self.list_loop != 0i32 { w.write_with_tag(96, |w| w.write_int32(*&self.list_loop))?; }
+        if self.thermal != 0i32 { w.write_with_tag(104, |w| w.write_int32(*&self.thermal))?; }
+        if self.fault != 0i32 { w.write_with_tag(112, |w| w.write_int32(*&self.fault))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCalibration {
+    pub gain0: i32,
+    pub gain1: i32,
+    pub gain2: i32,
+    pub gain3: i32,
+    pub offset0: i32,
+    pub offset1: i32,
+    pub offset2: i32,
+    pub offset3: i32,
+}
+
+impl<'a> MessageRead<'a> for QCalibration {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.gain0 = r.read_int32(bytes)?,
+                Ok(16) => msg.gain1 = r.read_int32(bytes)?,
+                Ok(24) => msg.gain2 = r.read_int32(bytes)?,
+                Ok(32) => msg.gain3 = r.read_int32(bytes)?,
+                Ok(40) => msg.offset0 = r.read_sint32(bytes)?,
+                Ok(48) => msg.offset1 = r.read_sint32(bytes)?,
+                Ok(56) => msg.offset2 = r.read_sint32(bytes)?,
+                Ok(64) => msg.offset3 = r.read_sint32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCalibration {
+    fn get_size(&self) -> usize {
+        0
+        + if self.gain0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.gain0) as u64) }
+        + if self.gain1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.gain1) as u64) }
+        + if self.gain2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.gain2) as u64) }
+        + if self.gain3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.gain3) as u64) }
+        + if self.offset0 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.offset0)) }
+        + if self.offset1 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.offset1)) }
+        + if self.offset2 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.offset2)) }
+        + if self.offset3 == 0i32 { 0 } else { 1 + sizeof_sint32(*(&self.offset3)) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.gain0 != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.gain0))?; }
+        if self.gain1 != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.gain1))?; }
+        if self.gain2 != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.gain2))?; }
+        if self.gain3 != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.gain3))?; }
+        if self.offset0 != 0i32 { w.write_with_tag(40, |w| w.write_sint32(*&self.offset0))?; }
+        if self.offset1 != 0i32 { w.write_with_tag(48, |w| w.write_sint32(*&self.offset1))?; }
+        if self.offset2 != 0i32 { w.write_with_tag(56, |w| w.write_sint32(*&self.offset2))?; }
+        if self.offset3 != 0i32 { w.write_with_tag(64, |w| w.write_sint32(*&self.offset3))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QLimits {
+    pub max_current0: i32,
+    pub max_current1: i32,
+    pub max_current2: i32,
+    pub max_current3: i32,
+}
+
+impl<'a> MessageRead<'a> for QLimits {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.max_current0 = r.read_int32(bytes)?,
+                Ok(16) => msg.max_current1 = r.read_int32(bytes)?,
+                Ok(24) => msg.max_current2 = r.read_int32(bytes)?,
+                Ok(32) => msg.max_current3 = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QLimits {
+    fn get_size(&self) -> usize {
+        0
+        + if self.max_current0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_current0) as u64) }
+        + if self.max_current1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_current1) as u64) }
+        + if self.max_current2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_current2) as u64) }
+        + if self.max_current3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_current3) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.max_current0 != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.max_current0))?; }
+        if self.max_current1 != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.max_current1))?; }
+        if self.max_current2 != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.max_current2))?; }
+        if self.max_current3 != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.max_current3))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QFanCurve {
+    pub temp0: i32,
+    pub temp1: i32,
+    pub temp2: i32,
+    pub temp3: i32,
+    pub pwm0: i32,
+    pub pwm1: i32,
+    pub pwm2: i32,
+    pub pwm3: i32,
+}
+
+impl<'a> MessageRead<'a> for QFanCurve {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.temp0 = r.read_int32(bytes)?,
+                Ok(16) => msg.temp1 = r.read_int32(bytes)?,
+                Ok(24) => msg.temp2 = r.read_int32(bytes)?,
+                Ok(32) => msg.temp3 = r.read_int32(bytes)?,
+                Ok(40) => msg.pwm0 = r.read_int32(bytes)?,
+                Ok(48) => msg.pwm1 = r.read_int32(bytes)?,
+                Ok(56) => msg.pwm2 = r.read_int32(bytes)?,
+                Ok(64) => msg.pwm3 = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QFanCurve {
+    fn get_size(&self) -> usize {
+        0
+        + if self.temp0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.temp0) as u64) }
+        + if self.temp1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.temp1) as u64) }
+        + if self.temp2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.temp2) as u64) }
+        + if self.temp3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.temp3) as u64) }
+        + if self.pwm0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pwm0) as u64) }
+        + if self.pwm1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pwm1) as u64) }
+        + if self.pwm2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pwm2) as u64) }
+        + if self.pwm3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pwm3) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.temp0 != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.temp0))?; }
+        if self.temp1 != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.temp1))?; }
+        if self.temp2 != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.temp2))?; }
+        if self.temp3 != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.temp3))?; }
+        if self.pwm0 != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.pwm0))?; }
+        if self.pwm1 != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.pwm1))?; }
+        if self.pwm2 != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.pwm2))?; }
+        if self.pwm3 != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.pwm3))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QDefaults {
+    pub sdn: i32,
+    pub pwm: i32,
+    pub current0: i32,
+    pub current1: i32,
+    pub current2: i32,
+    pub current3: i32,
+}
+
+impl<'a> MessageRead<'a> for QDefaults {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.sdn = r.read_int32(bytes)?,
+                Ok(16) => msg.pwm = r.read_int32(bytes)?,
+                Ok(24) => msg.current0 = r.read_int32(bytes)?,
+                Ok(32) => msg.current1 = r.read_int32(bytes)?,
+                Ok(40) => msg.current2 = r.read_int32(bytes)?,
+                Ok(48) => msg.current3 = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QDefaults {
+    fn get_size(&self) -> usize {
+        0
+        + if self.sdn == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.sdn) as u64) }
+        + if self.pwm == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pwm) as u64) }
+        + if self.current0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current0) as u64) }
+        + if self.current1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current1) as u64) }
+        + if self.current2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current2) as u64) }
+        + if self.current3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current3) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.sdn != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.sdn))?; }
+        if self.pwm != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.pwm))?; }
+        if self.current0 != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.current0))?; }
+        if self.current1 != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.current1))?; }
+        if self.current2 != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.current2))?; }
+        if self.current3 != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.current3))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCv {
+    pub voltage: i32,
+    pub max_current: i32,
+    pub kp: i32,
+    pub ki: i32,
+}
+
+impl<'a> MessageRead<'a> for QCv {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.voltage = r.read_int32(bytes)?,
+                Ok(16) => msg.max_current = r.read_int32(bytes)?,
+                Ok(24) => msg.kp = r.read_int32(bytes)?,
+                Ok(32) => msg.ki = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCv {
+    fn get_size(&self) -> usize {
+        0
+        + if self.voltage == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voltage) as u64) }
+        + if self.max_current == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_current) as u64) }
+        + if self.kp == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.kp) as u64) }
+        + if self.ki == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.ki) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.voltage != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.voltage))?; }
+        if self.max_current != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.max_current))?; }
+        if self.kp != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.kp))?; }
+        if self.ki != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.ki))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCr {
+    pub resistance: i32,
+}
+
+impl<'a> MessageRead<'a> for QCr {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.resistance = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCr {
+    fn get_size(&self) -> usize {
+        0
+        + if self.resistance == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.resistance) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.resistance != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.resistance))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCp {
+    pub power: i32,
+}
+
+impl<'a> MessageRead<'a> for QCp {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.power = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCp {
+    fn get_size(&self) -> usize {
+        0
+        + if self.power == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.power != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.power))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QTransient {
+    pub current_a: i32,
+    pub current_b: i32,
+    pub period: i32,
+    pub duty: i32,
+    pub rise: i32,
+    pub fall: i32,
+    pub trigger: i32,
+}
+
+impl<'a> MessageRead<'a> for QTransient {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.current_a = r.read_int32(bytes)?,
+                Ok(16) => msg.current_b = r.read_int32(bytes)?,
+                Ok(24) => msg.period = r.read_int32(bytes)?,
+                Ok(32) => msg.duty = r.read_int32(bytes)?,
+                Ok(40) => msg.rise = r.read_int32(bytes)?,
+                Ok(48) => msg.fall = r.read_int32(bytes)?,
+                Ok(56) => msg.trigger = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QTransient {
+    fn get_size(&self) -> usize {
+        0
+        + if self.current_a == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current_a) as u64) }
+        + if self.current_b == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current_b) as u64) }
+        + if self.period == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.period) as u64) }
+        + if self.duty == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.duty) as u64) }
+        + if self.rise == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.rise) as u64) }
+        + if self.fall == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.fall) as u64) }
+        + if self.trigger == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.trigger) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.current_a != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.current_a))?; }
+        if self.current_b != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.current_b))?; }
+        if self.period != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.period))?; }
+        if self.duty != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.duty))?; }
+        if self.rise != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.rise))?; }
+        if self.fall != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.fall))?; }
+        if self.trigger != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.trigger))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QListStep {
+    pub index: i32,
+    pub mode: i32,
+    pub setpoint: i32,
+    pub dwell: i32,
+    pub slew: i32,
+}
+
+impl<'a> MessageRead<'a> for QListStep {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.index = r.read_int32(bytes)?,
+                Ok(16) => msg.mode = r.read_int32(bytes)?,
+                Ok(24) => msg.setpoint = r.read_int32(bytes)?,
+                Ok(32) => msg.dwell = r.read_int32(bytes)?,
+                Ok(40) => msg.slew = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QListStep {
+    fn get_size(&self) -> usize {
+        0
+        + if self.index == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.index) as u64) }
+        + if self.mode == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.mode) as u64) }
+        + if self.setpoint == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.setpoint) as u64) }
+        + if self.dwell == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dwell) as u64) }
+        + if self.slew == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.slew) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.index != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.index))?; }
+        if self.mode != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.mode))?; }
+        if self.setpoint != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.setpoint))?; }
+        if self.dwell != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.dwell))?; }
+        if self.slew != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.slew))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QListRun {
+    pub count: i32,
+    pub loops: i32,
+}
+
+impl<'a> MessageRead<'a> for QListRun {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.count = r.read_int32(bytes)?,
+                Ok(16) => msg.loops = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QListRun {
+    fn get_size(&self) -> usize {
+        0
+        + if self.count == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.count) as u64) }
+        + if self.loops == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.loops) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.count != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.count))?; }
+        if self.loops != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.loops))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QSweep {
+    pub mode: i32,
+    pub start: i32,
+    pub stop: i32,
+    pub points: i32,
+    pub dwell: i32,
+}
+
+impl<'a> MessageRead<'a> for QSweep {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.mode = r.read_int32(bytes)?,
+                Ok(16) => msg.start = r.read_int32(bytes)?,
+                Ok(24) => msg.stop = r.read_int32(bytes)?,
+                Ok(32) => msg.points = r.read_int32(bytes)?,
+                Ok(40) => msg.dwell = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QSweep {
+    fn get_size(&self) -> usize {
+        0
+        + if self.mode == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.mode) as u64) }
+        + if self.start == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.start) as u64) }
+        + if self.stop == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.stop) as u64) }
+        + if self.points == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.points) as u64) }
+        + if self.dwell == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dwell) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.mode != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.mode))?; }
+        if self.start != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.start))?; }
+        if self.stop != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.stop))?; }
+        if self.points != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.points))?; }
+        if self.dwell != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.dwell))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QSweepStatus {
+    pub state: i32,
+    pub count: i32,
+}
+
+impl<'a> MessageRead<'a> for QSweepStatus {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.state = r.read_int32(bytes)?,
+                Ok(16) => msg.count = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QSweepStatus {
+    fn get_size(&self) -> usize {
+        0
+        + if self.state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.state) as u64) }
+        + if self.count == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.count) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.state != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.state))?; }
+        if self.count != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.count))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QSweepPoint {
+    pub index: i32,
+    pub setpoint: i32,
+    pub voltage: i32,
+    pub current: i32,
+    pub temp: i32,
+}
+
+impl<'a> MessageRead<'a> for QSweepPoint {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.index = r.read_int32(bytes)?,
+                Ok(16) => msg.setpoint = r.read_int32(bytes)?,
+                Ok(24) => msg.voltage = r.read_int32(bytes)?,
+                Ok(32) => msg.current = r.read_int32(bytes)?,
+                Ok(40) => msg.temp = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QSweepPoint {
+    fn get_size(&self) -> usize {
+        0
+        + if self.index == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.index) as u64) }
+        + if self.setpoint == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.setpoint) as u64) }
+        + if self.voltage == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voltage) as u64) }
+        + if self.current == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current) as u64) }
+        + if self.temp == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.temp) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.index != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.index))?; }
+        if self.setpoint != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.setpoint))?; }
+        if self.voltage != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.voltage))?; }
+        if self.current != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.current))?; }
+        if self.temp != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.temp))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QOcp {
+    pub start: i32,
+    pub stop: i32,
+    pub step: i32,
+    pub dwell: i32,
+    pub threshold: i32,
+    pub drop: i32,
+}
+
+impl<'a> MessageRead<'a> for QOcp {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.start = r.read_int32(bytes)?,
+                Ok(16) => msg.stop = r.read_int32(bytes)?,
+                Ok(24) => msg.step = r.read_int32(bytes)?,
+                Ok(32) => msg.dwell = r.read_int32(bytes)?,
+                Ok(40) => msg.threshold = r.read_int32(bytes)?,
+                Ok(48) => msg.drop = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QOcp {
+    fn get_size(&self) -> usize {
+        0
+        + if self.start == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.start) as u64) }
+        + if self.stop == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.stop) as u64) }
+        + if self.step == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.step) as u64) }
+        + if self.dwell == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dwell) as u64) }
+        + if self.threshold == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.threshold) as u64) }
+        + if self.drop == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.drop) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.start != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.start))?; }
+        if self.stop != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.stop))?; }
+        if self.step != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.step))?; }
+        if self.dwell != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.dwell))?; }
+        if self.threshold != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.threshold))?; }
+        if self.drop != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.drop))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QOcpResult {
+    pub state: i32,
+    pub current: i32,
+    pub voltage: i32,
+    pub time: i32,
+}
+
+impl<'a> MessageRead<'a> for QOcpResult {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.state = r.read_int32(bytes)?,
+                Ok(16) => msg.current = r.read_int32(bytes)?,
+                Ok(24) => msg.voltage = r.read_int32(bytes)?,
+                Ok(32) => msg.time = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QOcpResult {
+    fn get_size(&self) -> usize {
+        0
+        + if self.state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.state) as u64) }
+        + if self.current == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current) as u64) }
+        + if self.voltage == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voltage) as u64) }
+        + if self.time == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.time) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.state != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.state))?; }
+        if self.current != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.current))?; }
+        if self.voltage != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.voltage))?; }
+        if self.time != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.time))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QDischarge {
+    pub mode: i32,
+    pub setpoint: i32,
+    pub cutoff: i32,
+    pub debounce: i32,
+}
+
+impl<'a> MessageRead<'a> for QDischarge {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.mode = r.read_int32(bytes)?,
+                Ok(16) => msg.setpoint = r.read_int32(bytes)?,
+                Ok(24) => msg.cutoff = r.read_int32(bytes)?,
+                Ok(32) => msg.debounce = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QDischarge {
+    fn get_size(&self) -> usize {
+        0
+        + if self.mode == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.mode) as u64) }
+        + if self.setpoint == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.setpoint) as u64) }
+        + if self.cutoff == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.cutoff) as u64) }
+        + if self.debounce == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.debounce) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.mode != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.mode))?; }
+        if self.setpoint != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.setpoint))?; }
+        if self.cutoff != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.cutoff))?; }
+        if self.debounce != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.debounce))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QDischargeStatus {
+    pub state: i32,
+    pub elapsed: i32,
+    pub charge: i32,
+    pub energy: i32,
+}
+
+impl<'a> MessageRead<'a> for QDischargeStatus {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.state = r.read_int32(bytes)?,
+                Ok(16) => msg.elapsed = r.read_int32(bytes)?,
+                Ok(24) => msg.charge = r.read_int32(bytes)?,
+                Ok(32) => msg.energy = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QDischargeStatus {
+    fn get_size(&self) -> usize {
+        0
+        + if self.state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.state) as u64) }
+        + if self.elapsed == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.elapsed) as u64) }
+        + if self.charge == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.charge) as u64) }
+        + if self.energy == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.energy) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.state != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.state))?; }
+        if self.elapsed != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.elapsed))?; }
+        if self.charge != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.charge))?; }
+        if self.energy != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.energy))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QMppt {
+    pub step: i32,
+    pub interval: i32,
+}
+
+impl<'a> MessageRead<'a> for QMppt {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.step = r.read_int32(bytes)?,
+                Ok(16) => msg.interval = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QMppt {
+    fn get_size(&self) -> usize {
+        0
+        + if self.step == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.step) as u64) }
+        + if self.interval == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.interval) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.step != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.step))?; }
+        if self.interval != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.interval))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QMpptStatus {
+    pub state: i32,
+    pub voltage: i32,
+    pub current: i32,
+    pub power: i32,
+    pub voc: i32,
+}
+
+impl<'a> MessageRead<'a> for QMpptStatus {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.state = r.read_int32(bytes)?,
+                Ok(16) => msg.voltage = r.read_int32(bytes)?,
+                Ok(24) => msg.current = r.read_int32(bytes)?,
+                Ok(32) => msg.power = r.read_int32(bytes)?,
+                Ok(40) => msg.voc = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QMpptStatus {
+    fn get_size(&self) -> usize {
+        0
+        + if self.state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.state) as u64) }
+        + if self.voltage == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voltage) as u64) }
+        + if self.current == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current) as u64) }
+        + if self.power == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power) as u64) }
+        + if self.voc == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voc) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.state != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.state))?; }
+        if self.voltage != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.voltage))?; }
+        if self.current != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.current))?; }
+        if self.power != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.power))?; }
+        if self.voc != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.voc))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QIvCurve {
+    pub points: i32,
+    pub dwell: i32,
+    pub min_voltage: i32,
+}
+
+impl<'a> MessageRead<'a> for QIvCurve {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.points = r.read_int32(bytes)?,
+                Ok(16) => msg.dwell = r.read_int32(bytes)?,
+                Ok(24) => msg.min_voltage = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QIvCurve {
+    fn get_size(&self) -> usize {
+        0
+        + if self.points == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.points) as u64) }
+        + if self.dwell == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.dwell) as u64) }
+        + if self.min_voltage == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.min_voltage) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.points != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.points))?; }
+        if self.dwell != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.dwell))?; }
+        if self.min_voltage != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.min_voltage))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QThermal {
+    pub warning: i32,
+    pub derate: i32,
+    pub trip: i32,
+}
+
+impl<'a> MessageRead<'a> for QThermal {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.warning = r.read_int32(bytes)?,
+                Ok(16) => msg.derate = r.read_int32(bytes)?,
+                Ok(24) => msg.trip = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QThermal {
+    fn get_size(&self) -> usize {
+        0
+        + if self.warning == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.warning) as u64) }
+        + if self.derate == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.derate) as u64) }
+        + if self.trip == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.trip) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.warning != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.warning))?; }
+        if self.derate != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.derate))?; }
+        if self.trip != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.trip))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QSoa {
+    pub time0: i32,
+    pub time1: i32,
+    pub time2: i32,
+    pub time3: i32,
+    pub power0: i32,
+    pub power1: i32,
+    pub power2: i32,
+    pub power3: i32,
+    pub max_temp: i32,
+    pub shutdown: i32,
+}
+
+impl<'a> MessageRead<'a> for QSoa {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.time0 = r.read_int32(bytes)?,
+                Ok(16) => msg.time1 = r.read_int32(bytes)?,
+                Ok(24) => msg.time2 = r.read_int32(bytes)?,
+                Ok(32) => msg.time3 = r.read_int32(bytes)?,
+                Ok(40) => msg.power0 = r.read_int32(bytes)?,
+                Ok(48) => msg.power1 = r.read_int32(bytes)?,
+                Ok(56) => msg.power2 = r.read_int32(bytes)?,
+                Ok(64) => msg.power3 = r.read_int32(bytes)?,
+                Ok(72) => msg.max_temp = r.read_int32(bytes)?,
+                Ok(80) => msg.shutdown = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QSoa {
+    fn get_size(&self) -> usize {
+        0
+        + if self.time0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.time0) as u64) }
+        + if self.time1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.time1) as u64) }
+        + if self.time2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.time2) as u64) }
+        + if self.time3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.time3) as u64) }
+        + if self.power0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power0) as u64) }
+        + if self.power1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power1) as u64) }
+        + if self.power2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power2) as u64) }
+        + if self.power3 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power3) as u64) }
+        + if self.max_temp == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_temp) as u64) }
+        + if self.shutdown == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.shutdown) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.time0 != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.time0))?; }
+        if self.time1 != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.time1))?; }
+        if self.time2 != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.time2))?; }
+        if self.time3 != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.time3))?; }
+        if self.power0 != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.power0))?; }
+        if self.power1 != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.power1))?; }
+        if self.power2 != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.power2))?; }
+        if self.power3 != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.power3))?; }
+        if self.max_temp != 0i32 { w.write_with_tag(72, |w| w.write_int32(*&self.max_temp))?; }
+        if self.shutdown != 0i32 { w.write_with_tag(80, |w| w.write_int32(*&self.shutdown))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QSoaStatus {
+    pub state: i32,
+    pub channel: i32,
+    pub point: i32,
+    pub power: i32,
+    pub limit: i32,
+}
+
+impl<'a> MessageRead<'a> for QSoaStatus {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.state = r.read_int32(bytes)?,
+                Ok(16) => msg.channel = r.read_int32(bytes)?,
+                Ok(24) => msg.point = r.read_int32(bytes)?,
+                Ok(32) => msg.power = r.read_int32(bytes)?,
+                Ok(40) => msg.limit = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QSoaStatus {
+    fn get_size(&self) -> usize {
+        0
+        + if self.state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.state) as u64) }
+        + if self.channel == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.channel) as u64) }
+        + if self.point == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.point) as u64) }
+        + if self.power == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.power) as u64) }
+        + if self.limit == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.limit) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.state != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.state))?; }
+        if self.channel != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.channel))?; }
+        if self.point != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.point))?; }
+        if self.power != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.power))?; }
+        if self.limit != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.limit))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCommTimeout {
+    pub timeout: i32,
+}
+
+impl<'a> MessageRead<'a> for QCommTimeout {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.timeout = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCommTimeout {
+    fn get_size(&self) -> usize {
+        0
+        + if self.timeout == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.timeout) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.timeout != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.timeout))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QInfo<'a> {
+    pub reset_cause: i32,
+    pub protocol_version: i32,
+    pub firmware_version: Cow<'a, str>,
+    pub git_hash: Cow<'a, str>,
+    pub build_date: Cow<'a, str>,
+    pub hardware_revision: i32,
+    pub uid: Cow<'a, [u8]>,
+    pub num_channels: i32,
+    pub capabilities: i32,
+    pub max_current: i32,
+    pub max_list_steps: i32,
+    pub max_sweep_points: i32,
+    pub max_capture_points: i32,
+    pub max_message: i32,
+}
+
+impl<'a> MessageRead<'a> for QInfo<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.reset_cause = r.read_int32(bytes)?,
+                Ok(16) => msg.protocol_version = r.read_int32(bytes)?,
+                Ok(26) => msg.firmware_version = r.read_string(bytes).map(Cow::Borrowed)?,
+                Ok(34) => msg.git_hash = r.read_string(bytes).map(Cow::Borrowed)?,
+                Ok(42) => msg.build_date = r.read_string(bytes).map(Cow::Borrowed)?,
+                Ok(48) => msg.hardware_revision = r.read_int32(bytes)?,
+                Ok(58) => msg.uid = r.read_bytes(bytes).map(Cow::Borrowed)?,
+                Ok(64) => msg.num_channels = r.read_int32(bytes)?,
+                Ok(72) => msg.capabilities = r.read_int32(bytes)?,
+                Ok(80) => msg.max_current = r.read_int32(bytes)?,
+                Ok(88) => msg.max_list_steps = r.read_int32(bytes)?,
+                Ok(96) => msg.max_sweep_points = r.read_int32(bytes)?,
+                Ok(104) => msg.max_capture_points = r.read_int32(bytes)?,
+                Ok(112) => msg.max_message = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for QInfo<'a> {
+    fn get_size(&self) -> usize {
+        0
+        + if self.reset_cause == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.reset_cause) as u64) }
+        + if self.protocol_version == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.protocol_version) as u64) }
+        + if self.firmware_version == "" { 0 } else { 1 + sizeof_len((&self.firmware_version).len()) }
+        + if self.git_hash == "" { 0 } else { 1 + sizeof_len((&self.git_hash).len()) }
+        + if self.build_date == "" { 0 } else { 1 + sizeof_len((&self.build_date).len()) }
+        + if self.hardware_revision == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.hardware_revision) as u64) }
+        + if self.uid == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.uid).len()) }
+        + if self.num_channels == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.num_channels) as u64) }
+        + if self.capabilities == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.capabilities) as u64) }
+        + if self.max_current == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_current) as u64) }
+        + if self.max_list_steps == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_list_steps) as u64) }
+        + if self.max_sweep_points == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_sweep_points) as u64) }
+        + if self.max_capture_points == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_capture_points) as u64) }
+        + if self.max_message == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max_message) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.reset_cause != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.reset_cause))?; }
+        if self.protocol_version != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.protocol_version))?; }
+        if self.firmware_version != "" { w.write_with_tag(26, |w| w.write_string(&**&self.firmware_version))?; }
+        if self.git_hash != "" { w.write_with_tag(34, |w| w.write_string(&**&self.git_hash))?; }
+        if self.build_date != "" { w.write_with_tag(42, |w| w.write_string(&**&self.build_date))?; }
+        if self.hardware_revision != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.hardware_revision))?; }
+        if self.uid != Cow::Borrowed(b"") { w.write_with_tag(58, |w| w.write_bytes(&**&self.uid))?; }
+        if self.num_channels != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.num_channels))?; }
+        if self.capabilities != 0i32 { w.write_with_tag(72, |w| w.write_int32(*&self.capabilities))?; }
+        if self.max_current != 0i32 { w.write_with_tag(80, |w| w.write_int32(*&self.max_current))?; }
+        if self.max_list_steps != 0i32 { w.write_with_tag(88, |w| w.write_int32(*&self.max_list_steps))?; }
+        if self.max_sweep_points != 0i32 { w.write_with_tag(96, |w| w.write_int32(*&self.max_sweep_points))?; }
+        if self.max_capture_points != 0i32 { w.write_with_tag(104, |w| w.write_int32(*&self.max_capture_points))?; }
+        if self.max_message != 0i32 { w.write_with_tag(112, |w| w.write_int32(*&self.max_message))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QAwd {
+    pub current: i32,
+    pub voltage_low: i32,
+    pub voltage_high: i32,
+}
+
+impl<'a> MessageRead<'a> for QAwd {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.current = r.read_int32(bytes)?,
+                Ok(16) => msg.voltage_low = r.read_int32(bytes)?,
+                Ok(24) => msg.voltage_high = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QAwd {
+    fn get_size(&self) -> usize {
+        0
+        + if self.current == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.current) as u64) }
+        + if self.voltage_low == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voltage_low) as u64) }
+        + if self.voltage_high == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.voltage_high) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.current != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.current))?; }
+        if self.voltage_low != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.voltage_low))?; }
+        if self.voltage_high != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.voltage_high))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QStatistics {
+    pub index: i32,
+    pub average: i32,
+    pub min: i32,
+    pub max: i32,
+    pub rms: i32,
+    pub count: i32,
+    pub window: i32,
+}
+
+impl<'a> MessageRead<'a> for QStatistics {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.index = r.read_int32(bytes)?,
+                Ok(16) => msg.average = r.read_int32(bytes)?,
+                Ok(24) => msg.min = r.read_int32(bytes)?,
+                Ok(32) => msg.max = r.read_int32(bytes)?,
+                Ok(40) => msg.rms = r.read_int32(bytes)?,
+                Ok(48) => msg.count = r.read_int32(bytes)?,
+                Ok(56) => msg.window = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QStatistics {
+    fn get_size(&self) -> usize {
+        0
+        + if self.index == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.index) as u64) }
+        + if self.average == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.average) as u64) }
+        + if self.min == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.min) as u64) }
+        + if self.max == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.max) as u64) }
+        + if self.rms == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.rms) as u64) }
+        + if self.count == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.count) as u64) }
+        + if self.window == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.window) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.index != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.index))?; }
+        if self.average != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.average))?; }
+        if self.min != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.min))?; }
+        if self.max != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.max))?; }
+        if self.rms != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.rms))?; }
+        if self.count != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.count))?; }
+        if self.window != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.window))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QStatisticsWindow {
+    pub window: i32,
+}
+
+impl<'a> MessageRead<'a> for QStatisticsWindow {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.window = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QStatisticsWindow {
+    fn get_size(&self) -> usize {
+        0
+        + if self.window == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.window) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.window != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.window))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCapture {
+    pub source: i32,
+    pub edge: i32,
+    pub level: i32,
+    pub pre_trigger: i32,
+    pub decimation: i32,
+}
+
+impl<'a> MessageRead<'a> for QCapture {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.source = r.read_int32(bytes)?,
+                Ok(16) => msg.edge = r.read_int32(bytes)?,
+                Ok(24) => msg.level = r.read_int32(bytes)?,
+                Ok(32) => msg.pre_trigger = r.read_int32(bytes)?,
+                Ok(40) => msg.decimation = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCapture {
+    fn get_size(&self) -> usize {
+        0
+        + if self.source == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.source) as u64) }
+        + if self.edge == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.edge) as u64) }
+        + if self.level == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.level) as u64) }
+        + if self.pre_trigger == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pre_trigger) as u64) }
+        + if self.decimation == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.decimation) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.source != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.source))?; }
+        if self.edge != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.edge))?; }
+        if self.level != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.level))?; }
+        if self.pre_trigger != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.pre_trigger))?; }
+        if self.decimation != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.decimation))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCaptureStatus {
+    pub state: i32,
+    pub count: i32,
+    pub pre_trigger: i32,
+    pub decimation: i32,
+}
+
+impl<'a> MessageRead<'a> for QCaptureStatus {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.state = r.read_int32(bytes)?,
+                Ok(16) => msg.count = r.read_int32(bytes)?,
+                Ok(24) => msg.pre_trigger = r.read_int32(bytes)?,
+                Ok(32) => msg.decimation = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QCaptureStatus {
+    fn get_size(&self) -> usize {
+        0
+        + if self.state == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.state) as u64) }
+        + if self.count == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.count) as u64) }
+        + if self.pre_trigger == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.pre_trigger) as u64) }
+        + if self.decimation == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.decimation) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.state != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.state))?; }
+        if self.count != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.count))?; }
+        if self.pre_trigger != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.pre_trigger))?; }
+        if self.decimation != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.decimation))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QCaptureData<'a> {
+    pub offset: i32,
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> MessageRead<'a> for QCaptureData<'a> {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.offset = r.read_int32(bytes)?,
+                Ok(18) => msg.data = r.read_bytes(bytes).map(Cow::Borrowed)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl<'a> MessageWrite for QCaptureData<'a> {
+    fn get_size(&self) -> usize {
+        0
+        + if self.offset == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.offset) as u64) }
+        + if self.data == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.data).len()) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.offset != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.offset))?; }
+        if self.data != Cow::Borrowed(b"") { w.write_with_tag(18, |w| w.write_bytes(&**&self.data))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QMcuCalibration {
+    pub vrefint_cal: i32,
+    pub ts_cal1: i32,
+    pub ts_cal2: i32,
+    pub valid: i32,
+    pub vrefint: i32,
+    pub vdda: i32,
+    pub mcu_temp: i32,
+    pub board_temp: i32,
+}
+
+impl<'a> MessageRead<'a> for QMcuCalibration {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.vrefint_cal = r.read_int32(bytes)?,
+                Ok(16) => msg.ts_cal1 = r.read_int32(bytes)?,
+                Ok(24) => msg.ts_cal2 = r.read_int32(bytes)?,
+                Ok(32) => msg.valid = r.read_int32(bytes)?,
+                Ok(40) => msg.vrefint = r.read_int32(bytes)?,
+                Ok(48) => msg.vdda = r.read_int32(bytes)?,
+                Ok(56) => msg.mcu_temp = r.read_int32(bytes)?,
+                Ok(64) => msg.board_temp = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QMcuCalibration {
+    fn get_size(&self) -> usize {
+        0
+        + if self.vrefint_cal == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.vrefint_cal) as u64) }
+        + if self.ts_cal1 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.ts_cal1) as u64) }
+        + if self.ts_cal2 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.ts_cal2) as u64) }
+        + if self.valid == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.valid) as u64) }
+        + if self.vrefint == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.vrefint) as u64) }
+        + if self.vdda == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.vdda) as u64) }
+        + if self.mcu_temp == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.mcu_temp) as u64) }
+        + if self.board_temp == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.board_temp) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.vrefint_cal != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.vrefint_cal))?; }
+        if self.ts_cal1 != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.ts_cal1))?; }
+        if self.ts_cal2 != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.ts_cal2))?; }
+        if self.valid != 0i32 { w.write_with_tag(32, |w| w.write_int32(*&self.valid))?; }
+        if self.vrefint != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.vrefint))?; }
+        if self.vdda != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.vdda))?; }
+        if self.mcu_temp != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.mcu_temp))?; }
+        if self.board_temp != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.board_temp))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QRawState {
     pub ch0: i32,
     pub ch1: i32,
     pub ch2: i32,
@@ -157,10 +1757,10 @@ pub struct QState {
     pub cal: i32,
     pub v: i32,
     pub temp: i32,
-    pub sdn: i32,
+    pub ts: i32,
 }
 
-impl<'a> MessageRead<'a> for QState {
+impl<'a> MessageRead<'a> for QRawState {
     fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
         let mut msg = Self::default();
         while !r.is_eof() {
@@ -172,7 +1772,7 @@ impl<'a> MessageRead<'a> for QState {
                 Ok(40) => msg.cal = r.read_int32(bytes)?,
                 Ok(48) => msg.v = r.read_int32(bytes)?,
                 Ok(56) => msg.temp = r.read_int32(bytes)?,
-                Ok(64) => msg.sdn = r.read_int32(bytes)?,
+                Ok(64) => msg.ts = r.read_int32(bytes)?,
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -181,7 +1781,7 @@ impl<'a> MessageRead<'a> for QState {
     }
 }
 
-impl MessageWrite for QState {
+impl MessageWrite for QRawState {
     fn get_size(&self) -> usize {
         0
         + if self.ch0 == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.ch0) as u64) }
@@ -191,7 +1791,7 @@ impl MessageWrite for QState {
         + if self.cal == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.cal) as u64) }
         + if self.v == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.v) as u64) }
         + if self.temp == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.temp) as u64) }
-        + if self.sdn == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.sdn) as u64) }
+        + if self.ts == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.ts) as u64) }
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
@@ -202,7 +1802,83 @@ impl MessageWrite for QState {
         if self.cal != 0i32 { w.write_with_tag(40, |w| w.write_int32(*&self.cal))?; }
         if self.v != 0i32 { w.write_with_tag(48, |w| w.write_int32(*&self.v))?; }
         if self.temp != 0i32 { w.write_with_tag(56, |w| w.write_int32(*&self.temp))?; }
-        if self.sdn != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.sdn))?; }
+        if self.ts != 0i32 { w.write_with_tag(64, |w| w.write_int32(*&self.ts))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QSubscribe {
+    pub interval: i32,
+    pub events: i32,
+}
+
+impl<'a> MessageRead<'a> for QSubscribe {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.interval = r.read_int32(bytes)?,
+                Ok(16) => msg.events = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QSubscribe {
+    fn get_size(&self) -> usize {
+        0
+        + if self.interval == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.interval) as u64) }
+        + if self.events == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.events) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.interval != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.interval))?; }
+        if self.events != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.events))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct QEvent {
+    pub event: i32,
+    pub value: i32,
+    pub time: i32,
+}
+
+impl<'a> MessageRead<'a> for QEvent {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(8) => msg.event = r.read_int32(bytes)?,
+                Ok(16) => msg.value = r.read_int32(bytes)?,
+                Ok(24) => msg.time = r.read_int32(bytes)?,
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for QEvent {
+    fn get_size(&self) -> usize {
+        0
+        + if self.event == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.event) as u64) }
+        + if self.value == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.value) as u64) }
+        + if self.time == 0i32 { 0 } else { 1 + sizeof_varint(*(&self.time) as u64) }
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if self.event != 0i32 { w.write_with_tag(8, |w| w.write_int32(*&self.event))?; }
+        if self.value != 0i32 { w.write_with_tag(16, |w| w.write_int32(*&self.value))?; }
+        if self.time != 0i32 { w.write_with_tag(24, |w| w.write_int32(*&self.time))?; }
         Ok(())
     }
 }
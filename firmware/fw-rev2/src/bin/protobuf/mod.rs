@@ -1,3 +1,2 @@
 // Automatically generated mod.rs
 pub mod coms;
-
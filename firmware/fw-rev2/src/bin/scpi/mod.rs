@@ -0,0 +1,350 @@
+//! SCPI subset for the second CDC-ACM port and the USBTMC interface.
+//!
+//! A line holds one or more commands separated by `;`. Keywords are case insensitive and
+//! accepted in the short (`MEAS`) and the long form (`MEASure`), the optional `SOURce:`,
+//! `INPut:STATe` and `SYSTem:ERRor:NEXT?` nodes may be left out. Values are in A, V, Ω and W
+//! with an optional unit suffix, e.g. `CURR 500mA` or `RES 1.5E3OHM`.
+
+use core::fmt;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScpiError {
+    Syntax = -102,
+    ParameterNotAllowed = -108,
+    MissingParameter = -109,
+    UndefinedHeader = -113,
+    NumericData = -120,
+    InvalidSuffix = -131,
+    // the load can't be switched on with a latched fault
+    SettingsConflict = -221,
+    DataOutOfRange = -222,
+    TooMuchData = -223,
+    IllegalValue = -224,
+    QueueOverflow = -350,
+    InputOverrun = -363,
+    // a new command arrived before the answer to the last query was read (USBTMC)
+    QueryInterrupted = -410,
+    // the host asked for an answer without sending a query (USBTMC)
+    QueryUnterminated = -420,
+}
+
+impl ScpiError {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            ScpiError::Syntax => "Syntax error",
+            ScpiError::ParameterNotAllowed => "Parameter not allowed",
+            ScpiError::MissingParameter => "Missing parameter",
+            ScpiError::UndefinedHeader => "Undefined header",
+            ScpiError::NumericData => "Numeric data error",
+            ScpiError::InvalidSuffix => "Invalid suffix",
+            ScpiError::SettingsConflict => "Settings conflict",
+            ScpiError::DataOutOfRange => "Data out of range",
+            ScpiError::TooMuchData => "Too much data",
+            ScpiError::IllegalValue => "Illegal parameter value",
+            ScpiError::QueueOverflow => "Queue overflow",
+            ScpiError::InputOverrun => "Input buffer overrun",
+            ScpiError::QueryInterrupted => "Query INTERRUPTED",
+            ScpiError::QueryUnterminated => "Query UNTERMINATED",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Function {
+    Current,
+    Voltage,
+    Resistance,
+    Power,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Quantity {
+    Current,
+    Voltage,
+    Power,
+    Temperature,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Command {
+    Identify,
+    Reset,
+    OperationComplete,
+    Clear,
+    NextError,
+    /// setpoint in mA, mV, mΩ or mW
+    Set(Function, i32),
+    Input(bool),
+    InputQuery,
+    Measure(Quantity),
+}
+
+const FUNCTIONS: [(&str, &str, Function, &str); 4] = [
+    ("CURR", "CURRENT", Function::Current, "A"),
+    ("VOLT", "VOLTAGE", Function::Voltage, "V"),
+    ("RES", "RESISTANCE", Function::Resistance, "OHM"),
+    ("POW", "POWER", Function::Power, "W"),
+];
+
+const QUANTITIES: [(&str, &str, Quantity); 4] = [
+    ("CURR", "CURRENT", Quantity::Current),
+    ("VOLT", "VOLTAGE", Quantity::Voltage),
+    ("POW", "POWER", Quantity::Power),
+    ("TEMP", "TEMPERATURE", Quantity::Temperature),
+];
+
+const MAX_NODES: usize = 4;
+
+fn keyword(node: &str, short: &str, long: &str) -> bool {
+    node.eq_ignore_ascii_case(short) || node.eq_ignore_ascii_case(long)
+}
+
+/// Parses one command of a line, `None` if it is empty.
+pub fn parse(command: &str) -> Result<Option<Command>, ScpiError> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Ok(None);
+    }
+
+    let (header, parameter) = match command.find(|c: char| c.is_ascii_whitespace()) {
+        Some(pos) => (&command[..pos], command[pos..].trim()),
+        None => (command, ""),
+    };
+    let (header, query) = match header.strip_suffix('?') {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+
+    if let Some(common) = header.strip_prefix('*') {
+        let command = match query {
+            true if common.eq_ignore_ascii_case("IDN") => Command::Identify,
+            true if common.eq_ignore_ascii_case("OPC") => Command::OperationComplete,
+            false if common.eq_ignore_ascii_case("RST") => Command::Reset,
+            false if common.eq_ignore_ascii_case("CLS") => Command::Clear,
+            _ => return Err(ScpiError::UndefinedHeader),
+        };
+        return no_parameter(parameter, command);
+    }
+
+    let mut nodes = [""; MAX_NODES];
+    let mut count = 0;
+    for node in header.strip_prefix(':').unwrap_or(header).split(':') {
+        if count == MAX_NODES {
+            return Err(ScpiError::UndefinedHeader);
+        }
+        nodes[count] = node;
+        count += 1;
+    }
+    let mut nodes = &nodes[..count];
+    // the setpoints are in the default SOURce subsystem
+    if keyword(nodes[0], "SOUR", "SOURCE") {
+        nodes = &nodes[1..];
+    }
+
+    let is = |index: usize, short: &str, long: &str| {
+        nodes
+            .get(index)
+            .is_some_and(|node| keyword(node, short, long))
+    };
+
+    if nodes.len() == 1 && !query {
+        if let Some(&(_, _, function, unit)) = FUNCTIONS
+            .iter()
+            .find(|(short, long, ..)| is(0, short, long))
+        {
+            return Ok(Some(Command::Set(function, value(parameter, unit)?)));
+        }
+    }
+    if is(0, "INP", "INPUT") && (nodes.len() == 1 || (nodes.len() == 2 && is(1, "STAT", "STATE"))) {
+        if query {
+            return no_parameter(parameter, Command::InputQuery);
+        }
+        return Ok(Some(Command::Input(boolean(parameter)?)));
+    }
+    if nodes.len() == 2 && query && is(0, "MEAS", "MEASURE") {
+        if let Some(&(_, _, quantity)) = QUANTITIES
+            .iter()
+            .find(|(short, long, _)| is(1, short, long))
+        {
+            return no_parameter(parameter, Command::Measure(quantity));
+        }
+    }
+    if query
+        && is(0, "SYST", "SYSTEM")
+        && is(1, "ERR", "ERROR")
+        && (nodes.len() == 2 || (nodes.len() == 3 && is(2, "NEXT", "NEXT")))
+    {
+        return no_parameter(parameter, Command::NextError);
+    }
+    Err(ScpiError::UndefinedHeader)
+}
+
+fn no_parameter(parameter: &str, command: Command) -> Result<Option<Command>, ScpiError> {
+    match parameter.is_empty() {
+        true => Ok(Some(command)),
+        false => Err(ScpiError::ParameterNotAllowed),
+    }
+}
+
+fn boolean(parameter: &str) -> Result<bool, ScpiError> {
+    if parameter.is_empty() {
+        return Err(ScpiError::MissingParameter);
+    }
+    if parameter.eq_ignore_ascii_case("ON") || parameter == "1" {
+        Ok(true)
+    } else if parameter.eq_ignore_ascii_case("OFF") || parameter == "0" {
+        Ok(false)
+    } else {
+        Err(ScpiError::IllegalValue)
+    }
+}
+
+// splits a decimal number into its digits and a power of ten
+fn number(text: &str) -> Option<(i64, i32)> {
+    let (mantissa, mut exponent) = match text.find(['e', 'E']) {
+        Some(pos) => (&text[..pos], text[pos + 1..].parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (negative, digits) = match mantissa.as_bytes().first() {
+        Some(b'-') => (true, &mantissa[1..]),
+        Some(b'+') => (false, &mantissa[1..]),
+        _ => (false, mantissa),
+    };
+    if !(-30..=30).contains(&exponent) {
+        return None;
+    }
+
+    let mut value: i64 = 0;
+    let mut point = false;
+    let mut any = false;
+    for c in digits.bytes() {
+        match c {
+            b'0'..=b'9' => {
+                any = true;
+                // further digits are beyond the resolution of the setpoints
+                if value < 100_000_000_000_000 {
+                    value = value * 10 + (c - b'0') as i64;
+                    if point {
+                        exponent -= 1;
+                    }
+                } else if !point {
+                    exponent += 1;
+                }
+            }
+            b'.' if !point => point = true,
+            _ => return None,
+        }
+    }
+    if !any {
+        return None;
+    }
+    Some((if negative { -value } else { value }, exponent))
+}
+
+// power of ten of a unit suffix, a leading M is milli except for MOHM (mega)
+fn suffix(suffix: &str, unit: &str) -> Option<i32> {
+    if suffix.is_empty() || suffix.eq_ignore_ascii_case(unit) {
+        return Some(0);
+    }
+    let bytes = suffix.as_bytes();
+    if bytes.len() != unit.len() + 1 || !bytes[1..].eq_ignore_ascii_case(unit.as_bytes()) {
+        return None;
+    }
+    match (bytes[0].to_ascii_uppercase(), unit) {
+        (b'M', "OHM") => Some(6),
+        (b'K', _) => Some(3),
+        (b'M', _) => Some(-3),
+        (b'U', _) => Some(-6),
+        _ => None,
+    }
+}
+
+// value of a parameter in milli units, rounded to the nearest one
+fn value(parameter: &str, unit: &str) -> Result<i32, ScpiError> {
+    if parameter.is_empty() {
+        return Err(ScpiError::MissingParameter);
+    }
+    let end = parameter
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')))
+        .unwrap_or(parameter.len());
+    let (mantissa, exponent) = number(&parameter[..end]).ok_or(ScpiError::NumericData)?;
+    let scale = suffix(parameter[end..].trim(), unit).ok_or(ScpiError::InvalidSuffix)?;
+
+    let exponent = exponent + scale + 3;
+    let value = if exponent >= 0 {
+        10i64
+            .checked_pow(exponent as u32)
+            .and_then(|factor| mantissa.checked_mul(factor))
+            .ok_or(ScpiError::DataOutOfRange)?
+    } else if exponent < -18 {
+        0
+    } else {
+        let divisor = 10i64.pow(-exponent as u32);
+        (mantissa + mantissa.signum() * divisor / 2) / divisor
+    };
+    if !(0..=i32::MAX as i64).contains(&value) {
+        return Err(ScpiError::DataOutOfRange);
+    }
+    Ok(value as i32)
+}
+
+/// Errors in the order they happened, read with `SYST:ERR?`.
+pub const ERROR_QUEUE_LEN: usize = 8;
+
+pub struct ErrorQueue {
+    errors: [ScpiError; ERROR_QUEUE_LEN],
+    len: usize,
+}
+
+impl ErrorQueue {
+    pub const fn default() -> Self {
+        ErrorQueue {
+            errors: [ScpiError::QueueOverflow; ERROR_QUEUE_LEN],
+            len: 0,
+        }
+    }
+
+    /// Adds an error, in a full queue the newest one is replaced with a queue overflow.
+    pub fn push(&mut self, error: ScpiError) {
+        if self.len < ERROR_QUEUE_LEN {
+            self.errors[self.len] = error;
+            self.len += 1;
+        } else {
+            self.errors[ERROR_QUEUE_LEN - 1] = ScpiError::QueueOverflow;
+        }
+    }
+
+    /// Removes the oldest error.
+    pub fn pop(&mut self) -> Option<ScpiError> {
+        if self.len == 0 {
+            return None;
+        }
+        let error = self.errors[0];
+        self.errors.copy_within(1..self.len, 0);
+        self.len -= 1;
+        Some(error)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Formats a value in milli units as a decimal number of the base unit.
+pub struct Milli(pub i32);
+
+impl fmt::Display for Milli {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let value = self.0.unsigned_abs();
+        write!(f, "{}{}.{:03}", sign, value / 1000, value % 1000)
+    }
+}
@@ -0,0 +1,361 @@
+//! Persistent settings record in the data EEPROM.
+//!
+//! Record layout (little endian):
+//!
+//! | magic u32 | version u16 | words u16 | words * i32 | crc32 u32 |
+//!
+//! New fields are only ever appended. A record written by an older firmware is
+//! loaded and the missing fields are taken from the factory defaults.
+
+use defmt::*;
+use embassy_stm32::flash::{Blocking, Error, Flash};
+
+use crate::soa::SoaLimits;
+use crate::thermal::ThermalLimits;
+
+pub const NUM_CHANNELS: usize = 4;
+pub const NUM_FAN_POINTS: usize = 4;
+
+const MAGIC: u32 = 0x3244_4c45; // "ELD2"
+const VERSION: u16 = 5;
+const EEPROM_OFFSET: u32 = 0;
+
+const HEADER_SIZE: usize = 8;
+const NUM_WORDS: usize = 43;
+// records of newer firmware versions may be longer
+const MAX_WORDS: usize = 64;
+
+// 4mR shunt, 31.6k/1k sense amplifier, 3.3V reference, 16bit DAC
+// and the measured correction factor of 1.136
+const DEFAULT_DAC_GAIN: i32 = 2_942_800;
+
+/// Per-channel conversion of a current setpoint into a DAC code:
+/// `dac = offset + current_ma * gain / 1_000_000`, `gain` is in milli-LSB per ampere.
+#[derive(Clone, Copy)]
+pub struct ChannelCalibration {
+    pub gain: i32,
+    pub offset: i32,
+}
+
+impl ChannelCalibration {
+    pub const fn default() -> Self {
+        ChannelCalibration {
+            gain: DEFAULT_DAC_GAIN,
+            offset: 0,
+        }
+    }
+
+    pub fn current_to_dac(&self, current_ma: i32) -> i32 {
+        // a zero setpoint always turns the channel off, regardless of the offset
+        if current_ma <= 0 {
+            return 0;
+        }
+        let dac = self.offset as i64 + current_ma as i64 * self.gain as i64 / 1_000_000;
+        dac.clamp(0, 0xffff) as i32
+    }
+}
+
+/// Point of the fan curve, temperature in m°C and fan duty in percent.
+#[derive(Clone, Copy)]
+pub struct FanPoint {
+    pub temp: i32,
+    pub pwm: i32,
+}
+
+/// Window of the ADC analog watchdog, a measurement outside of it shuts the load down
+/// from the ADC interrupt. A value of 0 disables that limit.
+#[derive(Clone, Copy)]
+pub struct AwdLimits {
+    /// current of every channel in mA
+    pub current: i32,
+    /// input voltage in mV
+    pub voltage_low: i32,
+    pub voltage_high: i32,
+}
+
+impl AwdLimits {
+    pub const fn default() -> Self {
+        AwdLimits {
+            current: 0,
+            voltage_low: 0,
+            voltage_high: 0,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.current >= 0
+            && self.voltage_low >= 0
+            && self.voltage_high >= 0
+            && (self.voltage_high == 0 || self.voltage_low < self.voltage_high)
+    }
+}
+
+/// State that is applied at power-on.
+#[derive(Clone, Copy)]
+pub struct Defaults {
+    pub sdn: i32,
+    pub pwm: i32,
+    pub current: [i32; NUM_CHANNELS],
+}
+
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub calibration: [ChannelCalibration; NUM_CHANNELS],
+    /// per-channel current limit in mA
+    pub max_current: [i32; NUM_CHANNELS],
+    pub fan_curve: [FanPoint; NUM_FAN_POINTS],
+    pub defaults: Defaults,
+    /// over-temperature thresholds (added in version 2)
+    pub thermal: ThermalLimits,
+    /// per-MOSFET safe operating area (added in version 3)
+    pub soa: SoaLimits,
+    /// the load is shut down if the host sends no request for this many ms while it is
+    /// on, 0 disables the timeout (added in version 4)
+    pub comm_timeout: i32,
+    /// hardware overcurrent/overvoltage window (added in version 5)
+    pub awd: AwdLimits,
+}
+
+impl Settings {
+    pub const fn default() -> Self {
+        Settings {
+            calibration: [ChannelCalibration::default(); NUM_CHANNELS],
+            max_current: [25_000; NUM_CHANNELS],
+            fan_curve: [
+                FanPoint {
+                    temp: 25_000,
+                    pwm: 10,
+                },
+                FanPoint {
+                    temp: 40_000,
+                    pwm: 30,
+                },
+                FanPoint {
+                    temp: 55_000,
+                    pwm: 60,
+                },
+                FanPoint {
+                    temp: 70_000,
+                    pwm: 100,
+                },
+            ],
+            defaults: Defaults {
+                sdn: 1,
+                pwm: 0,
+                current: [0; NUM_CHANNELS],
+            },
+            thermal: ThermalLimits::default(),
+            soa: SoaLimits::default(),
+            comm_timeout: 0,
+            awd: AwdLimits::default(),
+        }
+    }
+
+    /// Fan duty in percent for a temperature in m°C, linearly interpolated
+    /// between the points of the fan curve.
+    pub fn fan_pwm(&self, temp: i32) -> i32 {
+        let curve = &self.fan_curve;
+        if temp <= curve[0].temp {
+            return curve[0].pwm;
+        }
+        for i in 1..NUM_FAN_POINTS {
+            let (lo, hi) = (curve[i - 1], curve[i]);
+            if temp < hi.temp {
+                return lo.pwm + (hi.pwm - lo.pwm) * (temp - lo.temp) / (hi.temp - lo.temp);
+            }
+        }
+        curve[NUM_FAN_POINTS - 1].pwm
+    }
+
+    /// DAC codes for per-channel current setpoints in mA, clamped to the channel limits.
+    pub fn dac_values(&self, current: &[i32; NUM_CHANNELS]) -> [i32; NUM_CHANNELS] {
+        let mut dac = [0i32; NUM_CHANNELS];
+        for i in 0..NUM_CHANNELS {
+            let current = current[i].min(self.max_current[i]);
+            dac[i] = self.calibration[i].current_to_dac(current);
+        }
+        dac
+    }
+
+    /// Plausibility check before settings from the host or the EEPROM are applied.
+    pub fn is_valid(&self) -> bool {
+        self.calibration.iter().all(|c| c.gain > 0)
+            && self.max_current.iter().all(|&m| m >= 0)
+            && self.fan_curve.iter().all(|p| (0..=100).contains(&p.pwm))
+            && self.fan_curve.windows(2).all(|p| p[0].temp < p[1].temp)
+            && (0..=100).contains(&self.defaults.pwm)
+            && self
+                .defaults
+                .current
+                .iter()
+                .zip(self.max_current.iter())
+                .all(|(&c, &m)| (0..=m).contains(&c))
+            && self.thermal.is_valid()
+            && self.soa.is_valid()
+            && self.comm_timeout >= 0
+            && self.awd.is_valid()
+    }
+
+    fn to_words(&self) -> [i32; NUM_WORDS] {
+        let mut words = [0i32; NUM_WORDS];
+        let mut i = 0;
+        let mut put = |v: i32| {
+            words[i] = v;
+            i += 1;
+        };
+
+        for c in self.calibration.iter() {
+            put(c.gain);
+            put(c.offset);
+        }
+        for &m in self.max_current.iter() {
+            put(m);
+        }
+        for p in self.fan_curve.iter() {
+            put(p.temp);
+            put(p.pwm);
+        }
+        put(self.defaults.sdn);
+        put(self.defaults.pwm);
+        for &c in self.defaults.current.iter() {
+            put(c);
+        }
+        put(self.thermal.warning);
+        put(self.thermal.derate);
+        put(self.thermal.trip);
+        for p in self.soa.points.iter() {
+            put(p.time);
+            put(p.power);
+        }
+        put(self.soa.max_temp);
+        put(self.soa.shutdown as i32);
+        put(self.comm_timeout);
+        put(self.awd.current);
+        put(self.awd.voltage_low);
+        put(self.awd.voltage_high);
+        words
+    }
+
+    fn from_words(stored: &[i32]) -> Self {
+        // fields missing in older records keep their default value
+        let mut words = Self::default().to_words();
+        let n = stored.len().min(NUM_WORDS);
+        words[..n].copy_from_slice(&stored[..n]);
+
+        let mut i = 0;
+        let mut get = || {
+            i += 1;
+            words[i - 1]
+        };
+
+        let mut s = Self::default();
+        for c in s.calibration.iter_mut() {
+            c.gain = get();
+            c.offset = get();
+        }
+        for m in s.max_current.iter_mut() {
+            *m = get();
+        }
+        for p in s.fan_curve.iter_mut() {
+            p.temp = get();
+            p.pwm = get();
+        }
+        s.defaults.sdn = get();
+        s.defaults.pwm = get();
+        for c in s.defaults.current.iter_mut() {
+            *c = get();
+        }
+        s.thermal.warning = get();
+        s.thermal.derate = get();
+        s.thermal.trip = get();
+        for p in s.soa.points.iter_mut() {
+            p.time = get();
+            p.power = get();
+        }
+        s.soa.max_temp = get();
+        s.soa.shutdown = get() != 0;
+        s.comm_timeout = get();
+        s.awd.current = get();
+        s.awd.voltage_low = get();
+        s.awd.voltage_high = get();
+        s
+    }
+}
+
+/// CRC-32 (IEEE 802.3)
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Loads the settings record, returns `None` if there is no valid record.
+pub fn load(flash: &mut Flash<'_, Blocking>) -> Option<Settings> {
+    let mut header = [0u8; HEADER_SIZE];
+    if let Err(e) = flash.eeprom_read(EEPROM_OFFSET, &mut header) {
+        error!("error reading settings header: {:?}", e);
+        return None;
+    }
+
+    let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    let num_words = u16::from_le_bytes([header[6], header[7]]) as usize;
+    if magic != MAGIC || num_words > MAX_WORDS {
+        warn!("no settings record found");
+        return None;
+    }
+
+    let mut buf = [0u8; HEADER_SIZE + MAX_WORDS * 4 + 4];
+    let len = HEADER_SIZE + num_words * 4;
+    if let Err(e) = flash.eeprom_read(EEPROM_OFFSET, &mut buf[..len + 4]) {
+        error!("error reading settings: {:?}", e);
+        return None;
+    }
+
+    let crc = u32::from_le_bytes([buf[len], buf[len + 1], buf[len + 2], buf[len + 3]]);
+    if crc != crc32(&buf[..len]) {
+        error!("settings record has an invalid crc");
+        return None;
+    }
+
+    let mut words = [0i32; MAX_WORDS];
+    for (i, w) in buf[HEADER_SIZE..len].chunks(4).enumerate() {
+        words[i] = i32::from_le_bytes([w[0], w[1], w[2], w[3]]);
+    }
+
+    // e.g. written by an earlier build with a bug, the defaults are safer than a panic at boot
+    let settings = Settings::from_words(&words[..num_words]);
+    if !settings.is_valid() {
+        error!("settings record version {} is not valid", version);
+        return None;
+    }
+
+    info!("loaded settings version {} ({} words)", version, num_words);
+    Some(settings)
+}
+
+pub fn store(flash: &mut Flash<'_, Blocking>, settings: &Settings) -> Result<(), Error> {
+    let mut buf = [0u8; HEADER_SIZE + NUM_WORDS * 4 + 4];
+    buf[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    buf[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    buf[6..8].copy_from_slice(&(NUM_WORDS as u16).to_le_bytes());
+    for (i, w) in settings.to_words().iter().enumerate() {
+        let pos = HEADER_SIZE + i * 4;
+        buf[pos..pos + 4].copy_from_slice(&w.to_le_bytes());
+    }
+
+    let len = HEADER_SIZE + NUM_WORDS * 4;
+    let crc = crc32(&buf[..len]);
+    buf[len..].copy_from_slice(&crc.to_le_bytes());
+
+    flash.eeprom_write(EEPROM_OFFSET, &buf)
+}
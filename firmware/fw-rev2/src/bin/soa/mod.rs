@@ -0,0 +1,203 @@
+//! Safe-operating-area limiting of the channel MOSFETs.
+//!
+//! The dissipation of every channel is computed from the input voltage and the channel
+//! current. A channel violates a point of the SOA table when its dissipation stays above
+//! the power of the point for longer than the pulse duration of the point. The powers of
+//! the table apply at 25°C and are derated linearly to zero at the maximum temperature.
+
+use crate::settings::NUM_CHANNELS;
+
+pub const NUM_SOA_POINTS: usize = 4;
+
+// temperature the powers of the SOA table are specified at in m°C
+const REFERENCE_TEMP: i32 = 25_000;
+
+// a clamped channel is held at this fraction of the violated power in per mille,
+// so measurement errors don't make it fire again right away
+const CLAMP_MARGIN: i32 = 900;
+
+// below this voltage in mV a power limit is not turned into a current limit
+const MIN_VOLTAGE: i32 = 100;
+
+/// Point of the SOA table, pulse duration in ms and dissipation in mW. The dissipation is
+/// checked on the averaged samples of the ADC, so durations below the sample period (4 ms)
+/// can't be resolved.
+#[derive(Clone, Copy)]
+pub struct SoaPoint {
+    pub time: i32,
+    pub power: i32,
+}
+
+#[derive(Clone, Copy)]
+pub struct SoaLimits {
+    /// sorted by ascending duration, the last point is the continuous rating
+    pub points: [SoaPoint; NUM_SOA_POINTS],
+    /// temperature in m°C at which no dissipation is allowed anymore
+    pub max_temp: i32,
+    /// shut the load down instead of clamping the channel
+    pub shutdown: bool,
+}
+
+impl SoaLimits {
+    pub const fn default() -> Self {
+        SoaLimits {
+            points: [
+                SoaPoint {
+                    time: 4,
+                    power: 130_000,
+                },
+                SoaPoint {
+                    time: 10,
+                    power: 100_000,
+                },
+                SoaPoint {
+                    time: 100,
+                    power: 50_000,
+                },
+                SoaPoint {
+                    time: 1000,
+                    power: 30_000,
+                },
+            ],
+            max_temp: 150_000,
+            shutdown: false,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.points.iter().all(|p| p.time >= 0 && p.power > 0)
+            && self
+                .points
+                .windows(2)
+                .all(|p| p[0].time < p[1].time && p[0].power >= p[1].power)
+            && self.max_temp > REFERENCE_TEMP
+    }
+
+    /// Power of a point in mW derated for a temperature in m°C.
+    pub fn power(&self, point: usize, temp: i32) -> i32 {
+        let power = self.points[point].power;
+        if temp <= REFERENCE_TEMP {
+            return power;
+        }
+        let scale = (self.max_temp - temp).max(0) as i64;
+        (power as i64 * scale / (self.max_temp - REFERENCE_TEMP) as i64) as i32
+    }
+}
+
+/// Channel and point of the SOA table that fired.
+#[derive(Clone, Copy)]
+pub struct Violation {
+    pub channel: usize,
+    pub point: usize,
+    /// dissipation of the channel in mW
+    pub power: i32,
+    /// derated power of the violated point in mW
+    pub limit: i32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SoaState {
+    Ok = 0,
+    // the violating channel is limited to the power of the violated point
+    Clamped = 1,
+    Shutdown = 2,
+}
+
+/// Last violation reported to the host.
+pub struct SoaStatus {
+    pub state: SoaState,
+    pub violation: Violation,
+}
+
+impl SoaStatus {
+    pub const fn default() -> Self {
+        SoaStatus {
+            state: SoaState::Ok,
+            violation: Violation {
+                channel: 0,
+                point: 0,
+                power: 0,
+                limit: 0,
+            },
+        }
+    }
+}
+
+pub struct SoaMonitor {
+    // time since each channel is above the power of each point in µs
+    over_since: [[Option<u64>; NUM_SOA_POINTS]; NUM_CHANNELS],
+    /// power limits of clamped channels in mW
+    pub caps: [Option<i32>; NUM_CHANNELS],
+}
+
+impl SoaMonitor {
+    pub const fn new() -> Self {
+        SoaMonitor {
+            over_since: [[None; NUM_SOA_POINTS]; NUM_CHANNELS],
+            caps: [None; NUM_CHANNELS],
+        }
+    }
+
+    /// Forgets the clamped channels, e.g. when the host sets new setpoints.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Checks the channel currents in mA at the input voltage in mV and the temperature
+    /// in m°C, returns the shortest violated point of the channel with the highest power.
+    pub fn check(
+        &mut self,
+        limits: &SoaLimits,
+        now: u64,
+        voltage: i32,
+        currents: &[i32; NUM_CHANNELS],
+        temp: i32,
+    ) -> Option<Violation> {
+        let mut violation: Option<Violation> = None;
+        for (channel, &current) in currents.iter().enumerate() {
+            let power = (voltage.max(0) as i64 * current.max(0) as i64 / 1000) as i32;
+            for point in 0..NUM_SOA_POINTS {
+                let limit = limits.power(point, temp);
+                if power <= limit {
+                    self.over_since[channel][point] = None;
+                    continue;
+                }
+
+                let since = *self.over_since[channel][point].get_or_insert(now);
+                let fired = now - since >= limits.points[point].time as u64 * 1000;
+                if fired && violation.map_or(true, |v| power > v.power) {
+                    violation = Some(Violation {
+                        channel,
+                        point,
+                        power,
+                        limit,
+                    });
+                }
+            }
+        }
+        violation
+    }
+
+    /// Limits the dissipation of the violating channel to the power of the violated point.
+    pub fn clamp(&mut self, violation: &Violation) {
+        let cap = violation.limit * CLAMP_MARGIN / 1000;
+        let channel = violation.channel;
+        self.caps[channel] = Some(self.caps[channel].map_or(cap, |c| c.min(cap)));
+        self.over_since[channel][violation.point] = None;
+    }
+
+    /// Per-channel current setpoints in mA limited to the clamped powers at the input
+    /// voltage in mV.
+    pub fn limit(&self, current: &[i32; NUM_CHANNELS], voltage: i32) -> [i32; NUM_CHANNELS] {
+        let mut limited = *current;
+        if voltage < MIN_VOLTAGE {
+            return limited;
+        }
+        for (c, cap) in limited.iter_mut().zip(self.caps.iter()) {
+            if let Some(cap) = cap {
+                *c = (*c).min((*cap as i64 * 1000 / voltage as i64) as i32);
+            }
+        }
+        limited
+    }
+}
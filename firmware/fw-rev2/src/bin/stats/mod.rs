@@ -0,0 +1,142 @@
+//! Measurement statistics of the channel currents and the input voltage.
+//!
+//! Every ADC scan is added, so the minimum and maximum show the ripple at the scan rate.
+//! The rolling average covers the last `window` scans, minimum, maximum and RMS cover all
+//! scans since the last reset.
+
+use crate::settings::NUM_CHANNELS;
+
+/// the four channel currents and the input voltage
+pub const NUM_VALUES: usize = NUM_CHANNELS + 1;
+pub const VOLTAGE: usize = NUM_CHANNELS;
+
+pub const MAX_WINDOW: usize = 64;
+pub const DEFAULT_WINDOW: usize = 16;
+
+/// Statistics of one value, currents in mA and the voltage in mV.
+#[derive(Clone, Copy)]
+pub struct Summary {
+    pub average: i32,
+    pub min: i32,
+    pub max: i32,
+    pub rms: i32,
+    /// number of scans since the last reset
+    pub count: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Accumulator {
+    // the last `window` values
+    history: [i32; MAX_WINDOW],
+    sum: i64,
+    min: i32,
+    max: i32,
+    sum_squares: u64,
+}
+
+impl Accumulator {
+    const fn default() -> Self {
+        Accumulator {
+            history: [0; MAX_WINDOW],
+            sum: 0,
+            min: i32::MAX,
+            max: i32::MIN,
+            sum_squares: 0,
+        }
+    }
+}
+
+pub struct Statistics {
+    window: usize,
+    // position of the next value in the history
+    pos: usize,
+    // values in the history, up to `window`
+    filled: usize,
+    count: u32,
+    values: [Accumulator; NUM_VALUES],
+}
+
+impl Statistics {
+    pub const fn default() -> Self {
+        Statistics {
+            window: DEFAULT_WINDOW,
+            pos: 0,
+            filled: 0,
+            count: 0,
+            values: [Accumulator::default(); NUM_VALUES],
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Sets the number of scans of the rolling average and resets all statistics.
+    pub fn set_window(&mut self, window: usize) -> bool {
+        if !(1..=MAX_WINDOW).contains(&window) {
+            return false;
+        }
+        *self = Statistics::default();
+        self.window = window;
+        true
+    }
+
+    pub fn reset(&mut self) {
+        let window = self.window;
+        *self = Statistics::default();
+        self.window = window;
+    }
+
+    /// Adds the currents and the voltage of one scan.
+    pub fn add(&mut self, currents: &[i32; NUM_CHANNELS], voltage: i32) {
+        for (i, acc) in self.values.iter_mut().enumerate() {
+            let value = if i == VOLTAGE { voltage } else { currents[i] };
+            if self.filled == self.window {
+                acc.sum -= acc.history[self.pos] as i64;
+            }
+            acc.history[self.pos] = value;
+            acc.sum += value as i64;
+            acc.min = acc.min.min(value);
+            acc.max = acc.max.max(value);
+            acc.sum_squares += (value as i64 * value as i64) as u64;
+        }
+        self.pos = (self.pos + 1) % self.window;
+        self.filled = (self.filled + 1).min(self.window);
+        self.count = self.count.saturating_add(1);
+    }
+
+    /// Statistics of a channel current (0 to 3) or the voltage (4).
+    pub fn summary(&self, index: usize) -> Summary {
+        let acc = &self.values[index];
+        if self.count == 0 {
+            return Summary {
+                average: 0,
+                min: 0,
+                max: 0,
+                rms: 0,
+                count: 0,
+            };
+        }
+        Summary {
+            average: (acc.sum / self.filled as i64) as i32,
+            min: acc.min,
+            max: acc.max,
+            rms: isqrt(acc.sum_squares / self.count as u64) as i32,
+            count: self.count,
+        }
+    }
+}
+
+fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    // Newton's method, starting above the root
+    let mut x = value;
+    let mut y = (x + value / x) / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
@@ -0,0 +1,157 @@
+//! Linear current or voltage sweep that records the averaged measurements of every step.
+//!
+//! Only the samples of the second half of the dwell time are averaged, so the load
+//! and the source under test have settled after the setpoint changed.
+
+use crate::list::{StepMode, Target};
+
+pub const MAX_POINTS: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SweepState {
+    Idle = 0,
+    Running = 1,
+    Done = 2,
+    Aborted = 3,
+}
+
+/// Averaged measurement of one step.
+#[derive(Clone, Copy)]
+pub struct Point {
+    pub setpoint: i32,
+    /// voltage in mV
+    pub voltage: i32,
+    /// total current in mA
+    pub current: i32,
+    /// temperature in m°C
+    pub temp: i32,
+}
+
+impl Point {
+    pub const fn default() -> Self {
+        Point {
+            setpoint: 0,
+            voltage: 0,
+            current: 0,
+            temp: 0,
+        }
+    }
+}
+
+pub struct SweepResults {
+    pub state: SweepState,
+    pub count: usize,
+    pub points: [Point; MAX_POINTS],
+}
+
+impl SweepResults {
+    pub const fn default() -> Self {
+        SweepResults {
+            state: SweepState::Idle,
+            count: 0,
+            points: [Point::default(); MAX_POINTS],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SweepConfig {
+    /// current in mA or voltage in mV
+    pub mode: StepMode,
+    pub start: i32,
+    pub stop: i32,
+    /// number of points including start and stop
+    pub points: i32,
+    /// dwell time per point in ms
+    pub dwell: i32,
+}
+
+impl SweepConfig {
+    pub fn is_valid(&self) -> bool {
+        matches!(self.mode, StepMode::Current | StepMode::Voltage)
+            && self.start >= 0
+            && self.stop >= 0
+            && (2..=MAX_POINTS as i32).contains(&self.points)
+            && self.dwell > 0
+    }
+}
+
+pub struct Sweep {
+    config: SweepConfig,
+    index: usize,
+    step_start: u64,
+    started: bool,
+    sum_voltage: i64,
+    sum_current: i64,
+    samples: i64,
+}
+
+impl Sweep {
+    pub fn new(config: SweepConfig, now: u64) -> Self {
+        Sweep {
+            config,
+            index: 0,
+            step_start: now,
+            started: false,
+            sum_voltage: 0,
+            sum_current: 0,
+            samples: 0,
+        }
+    }
+
+    fn setpoint(&self) -> i32 {
+        let c = &self.config;
+        let delta = (c.stop - c.start) as i64 * self.index as i64 / (c.points - 1) as i64;
+        c.start + delta as i32
+    }
+
+    fn dwell_us(&self) -> u64 {
+        self.config.dwell as u64 * 1000
+    }
+
+    /// Adds a sample with the voltage in mV and the total current in mA taken at `now`.
+    pub fn add_sample(&mut self, now: u64, voltage: i32, current: i32) {
+        if now >= self.step_start + self.dwell_us() / 2 {
+            self.sum_voltage += voltage as i64;
+            self.sum_current += current as i64;
+            self.samples += 1;
+        }
+    }
+
+    /// Stores the points of all steps that ended before `now` with the temperature in m°C
+    /// and returns the setpoint, `None` when the sweep has finished.
+    pub fn poll(&mut self, now: u64, temp: i32, results: &mut SweepResults) -> Option<Target> {
+        // the voltage regulator keeps running between the steps
+        let new_step = !self.started;
+        self.started = true;
+
+        while now >= self.step_start + self.dwell_us() {
+            let samples = self.samples.max(1);
+            let point = Point {
+                setpoint: self.setpoint(),
+                voltage: (self.sum_voltage / samples) as i32,
+                current: (self.sum_current / samples) as i32,
+                temp,
+            };
+            results.points[self.index] = point;
+            results.count = self.index + 1;
+
+            self.sum_voltage = 0;
+            self.sum_current = 0;
+            self.samples = 0;
+            self.step_start += self.dwell_us();
+            self.index += 1;
+            if self.index >= self.config.points as usize {
+                results.state = SweepState::Done;
+                return None;
+            }
+        }
+
+        Some(Target {
+            mode: self.config.mode,
+            setpoint: self.setpoint(),
+            new_step,
+            deadline: Some(self.step_start + self.dwell_us()),
+        })
+    }
+}
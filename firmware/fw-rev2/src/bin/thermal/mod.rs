@@ -0,0 +1,72 @@
+//! Over-temperature protection with a warning level, a derate band and a trip limit.
+//!
+//! Between the derate and the trip temperature all channel setpoints are scaled down
+//! linearly, at the trip temperature the load is shut down with a latched fault that
+//! only the host can clear.
+
+use crate::settings::NUM_CHANNELS;
+
+/// Full scale of the derating factor (per mille).
+pub const FULL_SCALE: i32 = 1000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ThermalState {
+    Normal = 0,
+    Warning = 1,
+    Derating = 2,
+    Tripped = 3,
+}
+
+/// Temperature thresholds in m°C.
+#[derive(Clone, Copy)]
+pub struct ThermalLimits {
+    pub warning: i32,
+    pub derate: i32,
+    pub trip: i32,
+}
+
+impl ThermalLimits {
+    pub const fn default() -> Self {
+        ThermalLimits {
+            warning: 60_000,
+            derate: 70_000,
+            trip: 85_000,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.warning <= self.derate && self.derate < self.trip
+    }
+
+    pub fn state(&self, temp: i32) -> ThermalState {
+        if temp >= self.trip {
+            ThermalState::Tripped
+        } else if temp > self.derate {
+            ThermalState::Derating
+        } else if temp >= self.warning {
+            ThermalState::Warning
+        } else {
+            ThermalState::Normal
+        }
+    }
+
+    /// Scale of the setpoints in per mille for a temperature in m°C.
+    pub fn derating(&self, temp: i32) -> i32 {
+        if temp <= self.derate {
+            FULL_SCALE
+        } else if temp >= self.trip {
+            0
+        } else {
+            let band = (self.trip - self.derate) as i64;
+            FULL_SCALE - (FULL_SCALE as i64 * (temp - self.derate) as i64 / band) as i32
+        }
+    }
+}
+
+/// Per-channel values scaled by a derating factor in per mille.
+pub fn derate(values: &[i32; NUM_CHANNELS], derating: i32) -> [i32; NUM_CHANNELS] {
+    if derating >= FULL_SCALE {
+        return *values;
+    }
+    values.map(|v| (v as i64 * derating as i64 / FULL_SCALE as i64) as i32)
+}
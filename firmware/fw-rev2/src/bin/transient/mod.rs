@@ -0,0 +1,142 @@
+//! Transient load mode that steps between two current levels.
+//!
+//! The generator is advanced from the TIM6 update interrupt in fixed ticks of
+//! [`TICK_US`], so the edges do not depend on the scheduling of the async tasks.
+
+// update rate of the DACs in transient mode
+pub const TICK_HZ: u32 = 10_000;
+pub const TICK_US: i32 = (1_000_000 / TICK_HZ) as i32;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Trigger {
+    // level B for `duty` of every period
+    Continuous,
+    // one level B pulse of `duty` of the period per trigger
+    Pulsed,
+    // every trigger switches between level A and level B
+    Toggled,
+}
+
+impl Trigger {
+    pub fn from_i32(value: i32) -> Option<Trigger> {
+        match value {
+            0 => Some(Trigger::Continuous),
+            1 => Some(Trigger::Pulsed),
+            2 => Some(Trigger::Toggled),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct TransientConfig {
+    /// total current of level A in mA
+    pub current_a: i32,
+    /// total current of level B in mA
+    pub current_b: i32,
+    /// period in µs
+    pub period: i32,
+    /// time at level B in per mille of the period
+    pub duty: i32,
+    /// slew rate of rising edges in mA/ms, 0 switches immediately
+    pub rise: i32,
+    /// slew rate of falling edges in mA/ms, 0 switches immediately
+    pub fall: i32,
+    pub trigger: Trigger,
+}
+
+impl TransientConfig {
+    pub fn is_valid(&self) -> bool {
+        self.current_a >= 0
+            && self.current_b >= 0
+            && (self.trigger == Trigger::Toggled || self.period >= 2 * TICK_US)
+            && (0..=1000).contains(&self.duty)
+            && self.rise >= 0
+            && self.fall >= 0
+    }
+
+    // time at level B in µs
+    fn width(&self) -> i32 {
+        (self.period as i64 * self.duty as i64 / 1000) as i32
+    }
+}
+
+pub struct Transient {
+    config: TransientConfig,
+    // time since the start of the period or pulse in µs
+    time: i32,
+    // level B is requested
+    high: bool,
+    // output current in µA, the slew rates are usually less than 1mA per tick
+    output: i64,
+}
+
+impl Transient {
+    pub fn new(config: TransientConfig) -> Self {
+        Transient {
+            config,
+            time: 0,
+            high: config.trigger == Trigger::Continuous,
+            output: config.current_a as i64 * 1000,
+        }
+    }
+
+    /// Continuous mode restarts the period, pulsed mode starts a pulse and
+    /// toggled mode switches to the other level.
+    pub fn trigger(&mut self) {
+        self.time = 0;
+        self.high = match self.config.trigger {
+            Trigger::Toggled => !self.high,
+            _ => true,
+        };
+    }
+
+    /// Level B is requested.
+    pub fn is_high(&self) -> bool {
+        self.high
+    }
+
+    /// Advances the generator by one tick and returns the total current in mA.
+    pub fn tick(&mut self) -> i32 {
+        let config = &self.config;
+        match config.trigger {
+            Trigger::Continuous => {
+                self.high = self.time < config.width();
+                self.time += TICK_US;
+                if self.time >= config.period {
+                    self.time = 0;
+                }
+            }
+            Trigger::Pulsed => {
+                if self.high {
+                    self.high = self.time < config.width();
+                    self.time += TICK_US;
+                }
+            }
+            Trigger::Toggled => {}
+        }
+
+        let target = if self.high {
+            config.current_b
+        } else {
+            config.current_a
+        } as i64
+            * 1000;
+        let slew = if target > self.output {
+            config.rise
+        } else {
+            config.fall
+        } as i64;
+        // mA/ms are µA/µs
+        let step = slew * TICK_US as i64;
+        self.output = if slew == 0 || (target - self.output).abs() <= step {
+            target
+        } else if target > self.output {
+            self.output + step
+        } else {
+            self.output - step
+        };
+
+        (self.output / 1000) as i32
+    }
+}
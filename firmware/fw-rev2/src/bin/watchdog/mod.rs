@@ -0,0 +1,48 @@
+//! Supervision of the tasks that feed the independent watchdog.
+//!
+//! Every supervised task checks in whenever its loop runs. The watchdog is only fed
+//! while all tasks checked in within their maximum silence, so a hung task resets the
+//! device just like a hung executor.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+#[derive(Clone, Copy)]
+pub enum Task {
+    Control = 0,
+    Adc = 1,
+    Temperature = 2,
+}
+
+const NUM_TASKS: usize = 3;
+
+// longest time in ms a task may not check in, the temperature task
+// only runs every 5 s while the load is off
+const MAX_SILENCE: [u32; NUM_TASKS] = [1_000, 1_000, 7_000];
+
+static LAST_CHECK_IN: [AtomicU32; NUM_TASKS] =
+    [AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)];
+
+/// Records that a task is alive at the time `now` in ms.
+pub fn check_in(task: Task, now: u32) {
+    LAST_CHECK_IN[task as usize].store(now, Ordering::Relaxed);
+}
+
+/// Returns the first task that didn't check in within its maximum silence.
+pub fn stalled(now: u32) -> Option<usize> {
+    (0..NUM_TASKS)
+        .find(|&i| now.wrapping_sub(LAST_CHECK_IN[i].load(Ordering::Relaxed)) > MAX_SILENCE[i])
+}
+
+/// Cause of the last reset from the RCC reset flags.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ResetCause {
+    Unknown = 0,
+    // power-on or brown-out
+    PowerOn = 1,
+    Pin = 2,
+    // software reset, also after a panic
+    Software = 3,
+    IndependentWatchdog = 4,
+    WindowWatchdog = 5,
+    LowPower = 6,
+}
@@ -13,7 +13,7 @@ _sym_db = _symbol_database.Default()
 
 
 
-DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\ncoms.proto\"0\n\x08QRequest\x12\n\n\x02id\x18\x01 \x01(\x05\x12\n\n\x02op\x18\x02 \x01(\x05\x12\x0c\n\x04\x64\x61ta\x18\x03 \x01(\x0c\"4\n\tQResponse\x12\n\n\x02id\x18\x01 \x01(\x05\x12\r\n\x05\x65rror\x18\x02 \x01(\x05\x12\x0c\n\x04\x64\x61ta\x18\x03 \x01(\x0c\"\\\n\x08QControl\x12\x0b\n\x03sdn\x18\x01 \x01(\x05\x12\x0b\n\x03pwm\x18\x02 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x30\x18\x03 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x31\x18\x04 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x32\x18\x05 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x33\x18\x06 \x01(\x05\"o\n\x06QState\x12\x0b\n\x03\x63h0\x18\x01 \x01(\x05\x12\x0b\n\x03\x63h1\x18\x02 \x01(\x05\x12\x0b\n\x03\x63h2\x18\x03 \x01(\x05\x12\x0b\n\x03\x63h3\x18\x04 \x01(\x05\x12\x0b\n\x03\x63\x61l\x18\x05 \x01(\x05\x12\t\n\x01v\x18\x06 \x01(\x05\x12\x0c\n\x04temp\x18\x07 \x01(\x05\x12\x0b\n\x03sdn\x18\x08 \x01(\x05\x62\x06proto3')
+DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\ncoms.proto\"0\n\x08QRequest\x12\n\n\x02id\x18\x01 \x01(\x05\x12\n\n\x02op\x18\x02 \x01(\x05\x12\x0c\n\x04\x64\x61ta\x18\x03 \x01(\x0c\"B\n\tQResponse\x12\n\n\x02id\x18\x01 \x01(\x05\x12\r\n\x05\x65rror\x18\x02 \x01(\x05\x12\x0c\n\x04\x64\x61ta\x18\x03 \x01(\x0c\x12\x0c\n\x04kind\x18\x04 \x01(\x05\"\xb1\x01\n\x08QControl\x12\x0b\n\x03sdn\x18\x01 \x01(\x05\x12\x0b\n\x03pwm\x18\x02 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x30\x18\x03 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x31\x18\x04 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x32\x18\x05 \x01(\x05\x12\x0c\n\x04\x64\x61\x63\x33\x18\x06 \x01(\x05\x12\x10\n\x08\x63urrent0\x18\x07 \x01(\x05\x12\x10\n\x08\x63urrent1\x18\x08 \x01(\x05\x12\x10\n\x08\x63urrent2\x18\t \x01(\x05\x12\x10\n\x08\x63urrent3\x18\n \x01(\x05\x12\x0b\n\x03raw\x18\x0b \x01(\x05\"\xf3\x01\n\x06QState\x12\x10\n\x08\x63urrent0\x18\x01 \x01(\x11\x12\x10\n\x08\x63urrent1\x18\x02 \x01(\x11\x12\x10\n\x08\x63urrent2\x18\x03 \x01(\x11\x12\x10\n\x08\x63urrent3\x18\x04 \x01(\x11\x12\x0f\n\x07voltage\x18\x05 \x01(\x11\x12\r\n\x05power\x18\x06 \x01(\x11\x12\x0c\n\x04temp\x18\x07 \x01(\x11\x12\x0b\n\x03sdn\x18\x08 \x01(\x05\x12\x0c\n\x04mode\x18\t \x01(\x05\x12\x12\n\nlist_state\x18\n \x01(\x05\x12\x11\n\tlist_step\x18\x0b \x01(\x05\x12\x11\n\tlist_loop\x18\x0c \x01(\x05\x12\x0f\n\x07thermal\x18\r \x01(\x05\x12\r\n\x05\x66\x61ult\x18\x0e \x01(\x05\"\x8e\x01\n\x0cQCalibration\x12\r\n\x05gain0\x18\x01 \x01(\x05\x12\r\n\x05gain1\x18\x02 \x01(\x05\x12\r\n\x05gain2\x18\x03 \x01(\x05\x12\r\n\x05gain3\x18\x04 \x01(\x05\x12\x0f\n\x07offset0\x18\x05 \x01(\x11\x12\x0f\n\x07offset1\x18\x06 \x01(\x11\x12\x0f\n\x07offset2\x18\x07 \x01(\x11\x12\x0f\n\x07offset3\x18\x08 \x01(\x11\"a\n\x07QLimits\x12\x14\n\x0cmax_current0\x18\x01 \x01(\x05\x12\x14\n\x0cmax_current1\x18\x02 \x01(\x05\x12\x14\n\x0cmax_current2\x18\x03 \x01(\x05\x12\x14\n\x0cmax_current3\x18\x04 \x01(\x05\"\x7f\n\tQFanCurve\x12\r\n\x05temp0\x18\x01 \x01(\x05\x12\r\n\x05temp1\x18\x02 \x01(\x05\x12\r\n\x05temp2\x18\x03 \x01(\x05\x12\r\n\x05temp3\x18\x04 \x01(\x05\x12\x0c\n\x04pwm0\x18\x05 \x01(\x05\x12\x0c\n\x04pwm1\x18\x06 \x01(\x05\x12\x0c\n\x04pwm2\x18\x07 \x01(\x05\x12\x0c\n\x04pwm3\x18\x08 \x01(\x05\"m\n\tQDefaults\x12\x0b\n\x03sdn\x18\x01 \x01(\x05\x12\x0b\n\x03pwm\x18\x02 \x01(\x05\x12\x10\n\x08\x63urrent0\x18\x03 \x01(\x05\x12\x10\n\x08\x63urrent1\x18\x04 \x01(\x05\x12\x10\n\x08\x63urrent2\x18\x05 \x01(\x05\x12\x10\n\x08\x63urrent3\x18\x06 \x01(\x05\"C\n\x03QCv\x12\x0f\n\x07voltage\x18\x01 \x01(\x05\x12\x13\n\x0bmax_current\x18\x02 \x01(\x05\x12\n\n\x02kp\x18\x03 \x01(\x05\x12\n\n\x02ki\x18\x04 \x01(\x05\"\x19\n\x03QCr\x12\x12\n\nresistance\x18\x01 \x01(\x05\"\x14\n\x03QCp\x12\r\n\x05power\x18\x01 \x01(\x05\"}\n\nQTransient\x12\x11\n\tcurrent_a\x18\x01 \x01(\x05\x12\x11\n\tcurrent_b\x18\x02 \x01(\x05\x12\x0e\n\x06period\x18\x03 \x01(\x05\x12\x0c\n\x04\x64uty\x18\x04 \x01(\x05\x12\x0c\n\x04rise\x18\x05 \x01(\x05\x12\x0c\n\x04\x66\x61ll\x18\x06 \x01(\x05\x12\x0f\n\x07trigger\x18\x07 \x01(\x05\"W\n\tQListStep\x12\r\n\x05index\x18\x01 \x01(\x05\x12\x0c\n\x04mode\x18\x02 \x01(\x05\x12\x10\n\x08setpoint\x18\x03 \x01(\x05\x12\r\n\x05\x64well\x18\x04 \x01(\x05\x12\x0c\n\x04slew\x18\x05 \x01(\x05\"(\n\x08QListRun\x12\r\n\x05\x63ount\x18\x01 \x01(\x05\x12\r\n\x05loops\x18\x02 \x01(\x05\"R\n\x06QSweep\x12\x0c\n\x04mode\x18\x01 \x01(\x05\x12\r\n\x05start\x18\x02 \x01(\x05\x12\x0c\n\x04stop\x18\x03 \x01(\x05\x12\x0e\n\x06points\x18\x04 \x01(\x05\x12\r\n\x05\x64well\x18\x05 \x01(\x05\",\n\x0cQSweepStatus\x12\r\n\x05state\x18\x01 \x01(\x05\x12\r\n\x05\x63ount\x18\x02 \x01(\x05\"^\n\x0bQSweepPoint\x12\r\n\x05index\x18\x01 \x01(\x05\x12\x10\n\x08setpoint\x18\x02 \x01(\x05\x12\x0f\n\x07voltage\x18\x03 \x01(\x05\x12\x0f\n\x07\x63urrent\x18\x04 \x01(\x05\x12\x0c\n\x04temp\x18\x05 \x01(\x05\"a\n\x04QOcp\x12\r\n\x05start\x18\x01 \x01(\x05\x12\x0c\n\x04stop\x18\x02 \x01(\x05\x12\x0c\n\x04step\x18\x03 \x01(\x05\x12\r\n\x05\x64well\x18\x04 \x01(\x05\x12\x11\n\tthreshold\x18\x05 \x01(\x05\x12\x0c\n\x04\x64rop\x18\x06 \x01(\x05\"K\n\nQOcpResult\x12\r\n\x05state\x18\x01 \x01(\x05\x12\x0f\n\x07\x63urrent\x18\x02 \x01(\x05\x12\x0f\n\x07voltage\x18\x03 \x01(\x05\x12\x0c\n\x04time\x18\x04 \x01(\x05\"N\n\nQDischarge\x12\x0c\n\x04mode\x18\x01 \x01(\x05\x12\x10\n\x08setpoint\x18\x02 \x01(\x05\x12\x0e\n\x06\x63utoff\x18\x03 \x01(\x05\x12\x10\n\x08\x64\x65\x62ounce\x18\x04 \x01(\x05\"R\n\x10QDischargeStatus\x12\r\n\x05state\x18\x01 \x01(\x05\x12\x0f\n\x07\x65lapsed\x18\x02 \x01(\x05\x12\x0e\n\x06\x63harge\x18\x03 \x01(\x05\x12\x0e\n\x06\x65nergy\x18\x04 \x01(\x05\"\'\n\x05QMppt\x12\x0c\n\x04step\x18\x01 \x01(\x05\x12\x10\n\x08interval\x18\x02 \x01(\x05\"Z\n\x0bQMpptStatus\x12\r\n\x05state\x18\x01 \x01(\x05\x12\x0f\n\x07voltage\x18\x02 \x01(\x05\x12\x0f\n\x07\x63urrent\x18\x03 \x01(\x05\x12\r\n\x05power\x18\x04 \x01(\x05\x12\x0b\n\x03voc\x18\x05 \x01(\x05\">\n\x08QIvCurve\x12\x0e\n\x06points\x18\x01 \x01(\x05\x12\r\n\x05\x64well\x18\x02 \x01(\x05\x12\x13\n\x0bmin_voltage\x18\x03 \x01(\x05\"9\n\x08QThermal\x12\x0f\n\x07warning\x18\x01 \x01(\x05\x12\x0e\n\x06\x64\x65rate\x18\x02 \x01(\x05\x12\x0c\n\x04trip\x18\x03 \x01(\x05\"\xa6\x01\n\x04QSoa\x12\r\n\x05time0\x18\x01 \x01(\x05\x12\r\n\x05time1\x18\x02 \x01(\x05\x12\r\n\x05time2\x18\x03 \x01(\x05\x12\r\n\x05time3\x18\x04 \x01(\x05\x12\x0e\n\x06power0\x18\x05 \x01(\x05\x12\x0e\n\x06power1\x18\x06 \x01(\x05\x12\x0e\n\x06power2\x18\x07 \x01(\x05\x12\x0e\n\x06power3\x18\x08 \x01(\x05\x12\x10\n\x08max_temp\x18\t \x01(\x05\x12\x10\n\x08shutdown\x18\n \x01(\x05\"Y\n\nQSoaStatus\x12\r\n\x05state\x18\x01 \x01(\x05\x12\x0f\n\x07\x63hannel\x18\x02 \x01(\x05\x12\r\n\x05point\x18\x03 \x01(\x05\x12\r\n\x05power\x18\x04 \x01(\x05\x12\r\n\x05limit\x18\x05 \x01(\x05\"\x1f\n\x0cQCommTimeout\x12\x0f\n\x07timeout\x18\x01 \x01(\x05\"\xc2\x02\n\x05QInfo\x12\x13\n\x0breset_cause\x18\x01 \x01(\x05\x12\x18\n\x10protocol_version\x18\x02 \x01(\x05\x12\x18\n\x10\x66irmware_version\x18\x03 \x01(\t\x12\x10\n\x08git_hash\x18\x04 \x01(\t\x12\x12\n\nbuild_date\x18\x05 \x01(\t\x12\x19\n\x11hardware_revision\x18\x06 \x01(\x05\x12\x0b\n\x03uid\x18\x07 \x01(\x0c\x12\x14\n\x0cnum_channels\x18\x08 \x01(\x05\x12\x14\n\x0c\x63\x61pabilities\x18\t \x01(\x05\x12\x13\n\x0bmax_current\x18\n \x01(\x05\x12\x16\n\x0emax_list_steps\x18\x0b \x01(\x05\x12\x18\n\x10max_sweep_points\x18\x0c \x01(\x05\x12\x1a\n\x12max_capture_points\x18\r \x01(\x05\x12\x13\n\x0bmax_message\x18\x0e \x01(\x05\"B\n\x04QAwd\x12\x0f\n\x07\x63urrent\x18\x01 \x01(\x05\x12\x13\n\x0bvoltage_low\x18\x02 \x01(\x05\x12\x14\n\x0cvoltage_high\x18\x03 \x01(\x05\"s\n\x0bQStatistics\x12\r\n\x05index\x18\x01 \x01(\x05\x12\x0f\n\x07\x61verage\x18\x02 \x01(\x05\x12\x0b\n\x03min\x18\x03 \x01(\x05\x12\x0b\n\x03max\x18\x04 \x01(\x05\x12\x0b\n\x03rms\x18\x05 \x01(\x05\x12\r\n\x05\x63ount\x18\x06 \x01(\x05\x12\x0e\n\x06window\x18\x07 \x01(\x05\"#\n\x11QStatisticsWindow\x12\x0e\n\x06window\x18\x01 \x01(\x05\"`\n\x08QCapture\x12\x0e\n\x06source\x18\x01 \x01(\x05\x12\x0c\n\x04\x65\x64ge\x18\x02 \x01(\x05\x12\r\n\x05level\x18\x03 \x01(\x05\x12\x13\n\x0bpre_trigger\x18\x04 \x01(\x05\x12\x12\n\ndecimation\x18\x05 \x01(\x05\"W\n\x0eQCaptureStatus\x12\r\n\x05state\x18\x01 \x01(\x05\x12\r\n\x05\x63ount\x18\x02 \x01(\x05\x12\x13\n\x0bpre_trigger\x18\x03 \x01(\x05\x12\x12\n\ndecimation\x18\x04 \x01(\x05\",\n\x0cQCaptureData\x12\x0e\n\x06offset\x18\x01 \x01(\x05\x12\x0c\n\x04\x64\x61ta\x18\x02 \x01(\x0c\"\x9c\x01\n\x0fQMcuCalibration\x12\x13\n\x0bvrefint_cal\x18\x01 \x01(\x05\x12\x0f\n\x07ts_cal1\x18\x02 \x01(\x05\x12\x0f\n\x07ts_cal2\x18\x03 \x01(\x05\x12\r\n\x05valid\x18\x04 \x01(\x05\x12\x0f\n\x07vrefint\x18\x05 \x01(\x05\x12\x0c\n\x04vdda\x18\x06 \x01(\x05\x12\x10\n\x08mcu_temp\x18\x07 \x01(\x05\x12\x12\n\nboard_temp\x18\x08 \x01(\x05\"q\n\tQRawState\x12\x0b\n\x03\x63h0\x18\x01 \x01(\x05\x12\x0b\n\x03\x63h1\x18\x02 \x01(\x05\x12\x0b\n\x03\x63h2\x18\x03 \x01(\x05\x12\x0b\n\x03\x63h3\x18\x04 \x01(\x05\x12\x0b\n\x03\x63\x61l\x18\x05 \x01(\x05\x12\t\n\x01v\x18\x06 \x01(\x05\x12\x0c\n\x04temp\x18\x07 \x01(\x05\x12\n\n\x02ts\x18\x08 \x01(\x05\".\n\nQSubscribe\x12\x10\n\x08interval\x18\x01 \x01(\x05\x12\x0e\n\x06\x65vents\x18\x02 \x01(\x05\"4\n\x06QEvent\x12\r\n\x05\x65vent\x18\x01 \x01(\x05\x12\r\n\x05value\x18\x02 \x01(\x05\x12\x0c\n\x04time\x18\x03 \x01(\x05\x62\x06proto3')
 
 _builder.BuildMessageAndEnumDescriptors(DESCRIPTOR, globals())
 _builder.BuildTopDescriptorsAndMessages(DESCRIPTOR, 'coms_pb2', globals())
@@ -23,9 +23,79 @@ if _descriptor._USE_C_DESCRIPTORS == False:
   _QREQUEST._serialized_start=14
   _QREQUEST._serialized_end=62
   _QRESPONSE._serialized_start=64
-  _QRESPONSE._serialized_end=116
-  _QCONTROL._serialized_start=118
-  _QCONTROL._serialized_end=210
-  _QSTATE._serialized_start=212
-  _QSTATE._serialized_end=323
+  _QRESPONSE._serialized_end=130
+  _QCONTROL._serialized_start=133
+  _QCONTROL._serialized_end=310
+  _QSTATE._serialized_start=313
+  _QSTATE._serialized_end=556
+  _QCALIBRATION._serialized_start=559
+  _QCALIBRATION._serialized_end=701
+  _QLIMITS._serialized_start=703
+  _QLIMITS._serialized_end=800
+  _QFANCURVE._serialized_start=802
+  _QFANCURVE._serialized_end=929
+  _QDEFAULTS._serialized_start=931
+  _QDEFAULTS._serialized_end=1040
+  _QCV._serialized_start=1042
+  _QCV._serialized_end=1109
+  _QCR._serialized_start=1111
+  _QCR._serialized_end=1136
+  _QCP._serialized_start=1138
+  _QCP._serialized_end=1158
+  _QTRANSIENT._serialized_start=1160
+  _QTRANSIENT._serialized_end=1285
+  _QLISTSTEP._serialized_start=1287
+  _QLISTSTEP._serialized_end=1374
+  _QLISTRUN._serialized_start=1376
+  _QLISTRUN._serialized_end=1416
+  _QSWEEP._serialized_start=1418
+  _QSWEEP._serialized_end=1500
+  _QSWEEPSTATUS._serialized_start=1502
+  _QSWEEPSTATUS._serialized_end=1546
+  _QSWEEPPOINT._serialized_start=1548
+  _QSWEEPPOINT._serialized_end=1642
+  _QOCP._serialized_start=1644
+  _QOCP._serialized_end=1741
+  _QOCPRESULT._serialized_start=1743
+  _QOCPRESULT._serialized_end=1818
+  _QDISCHARGE._serialized_start=1820
+  _QDISCHARGE._serialized_end=1898
+  _QDISCHARGESTATUS._serialized_start=1900
+  _QDISCHARGESTATUS._serialized_end=1982
+  _QMPPT._serialized_start=1984
+  _QMPPT._serialized_end=2023
+  _QMPPTSTATUS._serialized_start=2025
+  _QMPPTSTATUS._serialized_end=2115
+  _QIVCURVE._serialized_start=2117
+  _QIVCURVE._serialized_end=2179
+  _QTHERMAL._serialized_start=2181
+  _QTHERMAL._serialized_end=2238
+  _QSOA._serialized_start=2241
+  _QSOA._serialized_end=2407
+  _QSOASTATUS._serialized_start=2409
+  _QSOASTATUS._serialized_end=2498
+  _QCOMMTIMEOUT._serialized_start=2500
+  _QCOMMTIMEOUT._serialized_end=2531
+  _QINFO._serialized_start=2534
+  _QINFO._serialized_end=2856
+  _QAWD._serialized_start=2858
+  _QAWD._serialized_end=2924
+  _QSTATISTICS._serialized_start=2926
+  _QSTATISTICS._serialized_end=3041
+  _QSTATISTICSWINDOW._serialized_start=3043
+  _QSTATISTICSWINDOW._serialized_end=3078
+  _QCAPTURE._serialized_start=3080
+  _QCAPTURE._serialized_end=3176
+  _QCAPTURESTATUS._serialized_start=3178
+  _QCAPTURESTATUS._serialized_end=3265
+  _QCAPTUREDATA._serialized_start=3267
+  _QCAPTUREDATA._serialized_end=3311
+  _QMCUCALIBRATION._serialized_start=3314
+  _QMCUCALIBRATION._serialized_end=3470
+  _QRAWSTATE._serialized_start=3472
+  _QRAWSTATE._serialized_end=3585
+  _QSUBSCRIBE._serialized_start=3587
+  _QSUBSCRIBE._serialized_end=3633
+  _QEVENT._serialized_start=3635
+  _QEVENT._serialized_end=3687
 # @@protoc_insertion_point(module_scope)
@@ -6,38 +6,57 @@ import random
 import copy
 import os
 import math
+import struct
 import yaml
 import json
 import keyboard
 
 import coms_pb2
 import binascii
+import zlib
 
 import threading
+import collections
 
 from influx import Influx, Stats
 
-VREFINT = 1.224
 
+NUM_CHANNELS = 4
+
+# bits of the capabilities in the info
+CAPABILITIES = [
+    "constant_current", "constant_voltage", "constant_resistance", "constant_power",
+    "transient", "list", "sweep", "ocp", "discharge", "mppt", "capture", "statistics", "awd", "scpi",
+    "usbtmc",
+]
+
+# kinds of the messages from the device, responses and pushes of a subscription
+MSG_RESPONSE = 0
+MSG_STATE = 1
+MSG_EVENT = 2
+
+# names of the events pushed to a subscribed host, by id
+EVENTS = ["", "fault", "mode", "list_step", "list_state", "thermal", "soa", "shutdown"]
 
 class Control:
-    def __init__(self, sdn, pwm, dac):
+    def __init__(self, sdn, pwm, current):
         self.sdn = sdn
         self.pwm = pwm
-        self.dac0 = dac
-        self.dac1 = dac
-        self.dac2 = dac
-        self.dac3 = dac
+        # per-channel current in A, converted to DAC codes by the firmware
+        self.current0 = current
+        self.current1 = current
+        self.current2 = current
+        self.current3 = current
         pass
 
     def to_dict(self):
         return {
             'sdn': self.sdn,
             'pwm': self.pwm,
-            'dac0': self.dac0,
-            'dac1': self.dac1,
-            'dac2': self.dac2,
-            'dac3': self.dac3,
+            'current0': self.current0,
+            'current1': self.current1,
+            'current2': self.current2,
+            'current3': self.current3,
         }
 
 class State:
@@ -46,10 +65,20 @@ class State:
         self.ch1 = 0
         self.ch2 = 0
         self.ch3 = 0
-        self.cal = 0
         self.v = 0
+        self.p = 0
         self.temp = 0
         self.sdn = False
+        self.mode = 0
+        self.list_state = 0
+        self.list_step = 0
+        self.list_loop = 0
+        # 0 normal, 1 warning, 2 derating, 3 over the trip temperature
+        self.thermal = 0
+        # latched faults, bit 0 is over-temperature, bit 1 a temperature sensor that doesn't
+        # answer, bit 2 the SOA limiter, bit 3 the communication timeout, bit 4 a suspended or
+        # unplugged USB and bit 5 the analog watchdog
+        self.fault = 0
 
     def to_dict(self):
         return {
@@ -57,10 +86,16 @@ class State:
             'ch1': self.ch1,
             'ch2': self.ch2,
             'ch3': self.ch3,
-            'cal': self.cal,
             'v': self.v,
+            'p': self.p,
             'temp': self.temp,
             'sdn': self.sdn,
+            'mode': self.mode,
+            'list_state': self.list_state,
+            'list_step': self.list_step,
+            'list_loop': self.list_loop,
+            'thermal': self.thermal,
+            'fault': self.fault,
         }
 
 class ELoad:
@@ -73,6 +108,10 @@ class ELoad:
         self.control = Control(True, 0, 0)
         self.state = State()
 
+        # received bytes of an incomplete frame and pushes that weren't read yet
+        self._rx = b''
+        self.events = collections.deque(maxlen=1024)
+
         # Initialize serial communication
         self._serial_port_ctrl = serial.Serial(
             port=self.config['serial_port'],  # For GPIO serial communication use /dev/ttyS0
@@ -87,6 +126,55 @@ class ELoad:
         self.control.pwm = pwm
         self._send_control()
 
+    @staticmethod
+    def _varint(value):
+        out = b''
+        while value > 0x7f:
+            out += bytes([(value & 0x7f) | 0x80])
+            value >>= 7
+        return out + bytes([value])
+
+    @staticmethod
+    def _payload(data):
+        # the data of a response starts with its length as varint
+        pos = 0
+        while data[pos] & 0x80:
+            pos += 1
+        return data[pos + 1:]
+
+    @staticmethod
+    def _encode_frame(message):
+        # message and CRC-32, COBS encoded and terminated with a zero byte
+        data = message + zlib.crc32(message).to_bytes(4, 'little')
+        out = bytearray([0])
+        code_pos = 0
+        for byte in data:
+            if byte != 0:
+                out.append(byte)
+            if byte == 0 or len(out) - code_pos == 0xff:
+                out[code_pos] = len(out) - code_pos
+                code_pos = len(out)
+                out.append(0)
+        out[code_pos] = len(out) - code_pos
+        return bytes(out) + b'\x00'
+
+    @staticmethod
+    def _decode_frame(frame):
+        # returns the message of a frame without the delimiter, None if it is broken
+        data = bytearray()
+        pos = 0
+        while pos < len(frame):
+            code = frame[pos]
+            if code == 0 or pos + code > len(frame):
+                return None
+            data += frame[pos + 1:pos + code]
+            pos += code
+            if code < 0xff and pos < len(frame):
+                data.append(0)
+        if len(data) < 4 or zlib.crc32(bytes(data[:-4])) != int.from_bytes(data[-4:], 'little'):
+            return None
+        return bytes(data[:-4])
+
     def _request(self, op, params):
         request = coms_pb2.QRequest()
         request.id = self.reqid  # Set a unique ID for the request
@@ -96,74 +184,541 @@ class ELoad:
             request.data = params.SerializeToString()
         else:
             request.data = b'0x00'
-        request.data = bytes([len(request.data)]) + request.data
+        request.data = self._varint(len(request.data)) + request.data
 
-        serialized_request = request.SerializeToString()
-        serialized_request = bytes([len(serialized_request)]) + serialized_request
+        frame = self._encode_frame(request.SerializeToString())
 
-        logging.debug("-> %s", binascii.hexlify(serialized_request).decode('utf8'))
+        logging.debug("-> %s", binascii.hexlify(frame).decode('utf8'))
 
-        self._serial_port_ctrl.write(serialized_request)
+        # a leading delimiter ends whatever the device received before
+        self._serial_port_ctrl.write(b'\x00' + frame)
+
+        # skip broken frames and stale responses of earlier requests
+        while True:
+            response = self._read_response()
+            if response is None:
+                logging.error("timeout waiting for a response")
+                self.reqid += 1
+                return None
+
+            if response.id != self.reqid:
+                logging.error(f"request and response IDs mismatch! {response.id} vs {self.reqid}")
+                continue
 
-        response_len = self._serial_port_ctrl.read()
-        logging.debug(f"rx len: {response_len}")
-        if len(response_len) == 1 and response_len[0] == 0:
             self.reqid += 1
-            return coms_pb2.QResponse()
+            return response
+
+    def _read_response(self):
+        # reads frames until a response arrives, None on timeout
+        # state pushes and events of a subscription are queued in self.events
+        while True:
+            self._rx += self._serial_port_ctrl.read_until(b'\x00')
+            if not self._rx.endswith(b'\x00'):
+                return None
+            frame, self._rx = self._rx, b''
+
+            logging.debug("<- %s", binascii.hexlify(frame).decode('utf8'))
+
+            message = self._decode_frame(frame[:-1])
+            if message is None:
+                logging.error("invalid response frame")
+                continue
+
+            response = coms_pb2.QResponse()
+            response.ParseFromString(message)
+
+            if response.kind != MSG_RESPONSE:
+                self.events.append(response)
+                continue
+
+            return response
+
+    def _get(self, op, msg):
+        with self.serial_port_ctrl_lock:
+            resp = self._request(op, None)
+            if resp is None or resp.error != 0:
+                raise Exception(f"failed reading {type(msg).__name__}!")
+            msg.ParseFromString(self._payload(resp.data))
+            return msg
+
+    def _set(self, op, msg):
+        with self.serial_port_ctrl_lock:
+            resp = self._request(op, msg)
+            if resp is None or resp.error != 0:
+                raise Exception(f"failed writing {type(msg).__name__}!")
+
+    def set_voltage(self, voltage, max_current, kp=0, ki=0):
+        # constant voltage mode, voltage in V and total current limit in A
+        # kp (mA/V) and ki (mA/(V*s)) use the firmware defaults if 0
+        cv = coms_pb2.QCv()
+        cv.voltage = int(1000.0 * voltage)
+        cv.max_current = int(1000.0 * max_current)
+        cv.kp = kp
+        cv.ki = ki
+        self._set(12, cv)
+
+    def set_resistance(self, resistance):
+        # constant resistance mode, resistance in ohms
+        cr = coms_pb2.QCr()
+        cr.resistance = int(1000.0 * resistance)
+        self._set(13, cr)
+
+    def set_power(self, power):
+        # constant power mode, power in W
+        cp = coms_pb2.QCp()
+        cp.power = int(1000.0 * power)
+        self._set(14, cp)
+
+    def set_transient(self, current_a, current_b, period, duty=0.5, rise=0, fall=0, trigger="continuous"):
+        # transient mode stepping between current_a and current_b (A),
+        # period in s, duty as fraction of the period at current_b,
+        # slew rates in A/s (0 = step), trigger is continuous, pulsed or toggled
+        tr = coms_pb2.QTransient()
+        tr.current_a = int(1000.0 * current_a)
+        tr.current_b = int(1000.0 * current_b)
+        tr.period = int(1e6 * period)
+        tr.duty = int(1000.0 * duty)
+        # A/s is the same as mA/ms
+        tr.rise = int(rise)
+        tr.fall = int(fall)
+        tr.trigger = ["continuous", "pulsed", "toggled"].index(trigger)
+        self._set(15, tr)
+
+    def trigger_transient(self):
+        # starts a pulse, toggles the level or restarts the period
+        with self.serial_port_ctrl_lock:
+            resp = self._request(16, None)
+            if resp is None or resp.error != 0:
+                raise Exception("error trigger_transient")
+
+    def upload_list(self, steps):
+        # list of (mode, setpoint, dwell, slew) tuples, mode is current (A), voltage (V),
+        # resistance (ohms) or power (W), dwell in s and slew in setpoint units per s (0 = step)
+        modes = ["current", "voltage", "resistance", "power"]
+        for index, (mode, setpoint, dwell, slew) in enumerate(steps):
+            step = coms_pb2.QListStep()
+            step.index = index
+            step.mode = modes.index(mode)
+            # mA, mV, mOhm and mW
+            step.setpoint = int(1000.0 * setpoint)
+            step.dwell = int(1000.0 * dwell)
+            step.slew = int(1000.0 * slew)
+            self._set(17, step)
+
+    def run_list(self, count, loops=1):
+        # runs the first count uploaded steps, loops = 0 repeats forever, the load has to be
+        # switched on without a latched fault (set_shutdown(False)), else the run is rejected
+        run = coms_pb2.QListRun()
+        run.count = count
+        run.loops = loops
+        self._set(18, run)
+
+    def _list_command(self, op, name):
+        with self.serial_port_ctrl_lock:
+            resp = self._request(op, None)
+            if resp is None or resp.error != 0:
+                raise Exception(f"error {name}")
+
+    def pause_list(self):
+        self._list_command(19, "pause_list")
+
+    def resume_list(self):
+        self._list_command(20, "resume_list")
+
+    def abort_list(self):
+        self._list_command(21, "abort_list")
+
+    def sweep(self, mode, start, stop, points, dwell):
+        # linear sweep of the current (A) or voltage (V) in points steps, dwell in s per step,
+        # rejected unless the load is switched on without a latched fault
+        sweep = coms_pb2.QSweep()
+        sweep.mode = ["current", "voltage"].index(mode)
+        sweep.start = int(1000.0 * start)
+        sweep.stop = int(1000.0 * stop)
+        sweep.points = points
+        sweep.dwell = int(1000.0 * dwell)
+        self._set(22, sweep)
+
+    def abort_sweep(self):
+        self._list_command(23, "abort_sweep")
+
+    def get_sweep_status(self):
+        # state is 0 idle, 1 running, 2 done, 3 aborted
+        status = self._get(24, coms_pb2.QSweepStatus())
+        return {'state': status.state, 'count': status.count}
+
+    def get_sweep_results(self):
+        results = []
+        for index in range(self.get_sweep_status()['count']):
+            point = coms_pb2.QSweepPoint()
+            point.index = index
+            with self.serial_port_ctrl_lock:
+                resp = self._request(25, point)
+                if resp is None or resp.error != 0:
+                    raise Exception("failed reading sweep point!")
+                point.ParseFromString(self._payload(resp.data))
+            results.append({
+                'setpoint': point.setpoint / 1000.0,
+                'voltage': point.voltage / 1000.0,
+                'current': point.current / 1000.0,
+                'temp': point.temp / 1000.0,
+            })
+        return results
+
+    def ocp_test(self, start, stop, step, dwell, threshold=0, drop=0):
+        # ramps the current from start to stop (A) in steps with dwell (s) per step until
+        # the voltage falls below threshold (V) or drops by drop (0..1) of the start voltage,
+        # the load is shut down on a trip. The test doesn't switch the load on, it is rejected
+        # while the load is shut down or a fault is latched, so call set_shutdown(False) first
+        ocp = coms_pb2.QOcp()
+        ocp.start = int(1000.0 * start)
+        ocp.stop = int(1000.0 * stop)
+        ocp.step = int(1000.0 * step)
+        ocp.dwell = int(1000.0 * dwell)
+        ocp.threshold = int(1000.0 * threshold)
+        ocp.drop = int(100.0 * drop)
+        self._set(26, ocp)
+
+    def abort_ocp_test(self):
+        self._list_command(27, "abort_ocp_test")
+
+    def get_ocp_result(self):
+        # state is 0 idle, 1 running, 2 tripped, 3 no trip, 4 aborted
+        result = self._get(28, coms_pb2.QOcpResult())
+        return {
+            'state': result.state,
+            'current': result.current / 1000.0,
+            'voltage': result.voltage / 1000.0,
+            'time': result.time / 1e6,
+        }
 
-        response_data = self._serial_port_ctrl.read(response_len[0])
+    def discharge(self, mode, setpoint, cutoff, debounce=1.0):
+        # discharges at current (A), resistance (ohms) or power (W) until the voltage
+        # stays below cutoff (V) for debounce (s), rejected unless the load is switched on
+        # without a latched fault
+        discharge = coms_pb2.QDischarge()
+        discharge.mode = ["current", "voltage", "resistance", "power"].index(mode)
+        discharge.setpoint = int(1000.0 * setpoint)
+        discharge.cutoff = int(1000.0 * cutoff)
+        discharge.debounce = int(1000.0 * debounce)
+        self._set(29, discharge)
+
+    def abort_discharge(self):
+        self._list_command(30, "abort_discharge")
+
+    def get_discharge_status(self):
+        # state is 0 idle, 1 running, 2 cutoff reached, 3 aborted
+        status = self._get(31, coms_pb2.QDischargeStatus())
+        return {
+            'state': status.state,
+            'elapsed': status.elapsed / 1000.0,
+            'charge': status.charge / 1000.0,
+            'energy': status.energy / 1000.0,
+        }
 
-        logging.debug("<- %s", binascii.hexlify(response_data).decode('utf8'))
+    def mppt(self, step=0.1, interval=0.5):
+        # tracks the maximum power point by moving the voltage in steps (V) every interval (s),
+        # rejected unless the load is switched on without a latched fault
+        mppt = coms_pb2.QMppt()
+        mppt.step = int(1000.0 * step)
+        mppt.interval = int(1000.0 * interval)
+        self._set(32, mppt)
 
-        response = coms_pb2.QResponse()
-        response.ParseFromString(response_data)
+    def abort_mppt(self):
+        self._list_command(33, "abort_mppt")
 
-        if response.id != self.reqid:
-            logging.error(f"request and response IDs mismatch! {response.id} vs {self.reqid}")
+    def get_mppt_status(self):
+        # state is 0 idle, 1 measuring the open-circuit voltage, 2 tracking, 3 aborted
+        status = self._get(34, coms_pb2.QMpptStatus())
+        return {
+            'state': status.state,
+            'voltage': status.voltage / 1000.0,
+            'current': status.current / 1000.0,
+            'power': status.power / 1000.0,
+            'voc': status.voc / 1000.0,
+        }
 
-        self.reqid += 1
-        return response
+    def iv_curve(self, points, dwell, min_voltage=0.5):
+        # voltage sweep from the open-circuit voltage down to min_voltage (V), dwell in s per
+        # point, the results are read with get_sweep_status and get_sweep_results, rejected
+        # unless the load is switched on without a latched fault
+        curve = coms_pb2.QIvCurve()
+        curve.points = points
+        curve.dwell = int(1000.0 * dwell)
+        curve.min_voltage = int(1000.0 * min_voltage)
+        self._set(35, curve)
+
+    def get_calibration(self):
+        cal = self._get(3, coms_pb2.QCalibration())
+        return {
+            'gain': [cal.gain0, cal.gain1, cal.gain2, cal.gain3],
+            'offset': [cal.offset0, cal.offset1, cal.offset2, cal.offset3],
+        }
 
-    def _current_to_dac(self, current):
-        r_sense = 0.004 # 4mR
-        r1 = 31600.0 # 31.6k
-        r2 = 1000.0 # 1k
-        num_parallel = 4.0 # 4 mosfets
-        correct = 1.136363
+    def set_calibration(self, gain, offset):
+        cal = coms_pb2.QCalibration()
+        cal.gain0, cal.gain1, cal.gain2, cal.gain3 = gain
+        cal.offset0, cal.offset1, cal.offset2, cal.offset3 = offset
+        self._set(4, cal)
 
-        voltage = current * (r1 + r2) / r2 * r_sense
-        dac_value = (int(voltage / 3.3 * 65536.0 / num_parallel * correct)) & 0xffff
+    def get_limits(self):
+        limits = self._get(5, coms_pb2.QLimits())
+        return {
+            'max_current': [limits.max_current0 / 1000.0, limits.max_current1 / 1000.0,
+                            limits.max_current2 / 1000.0, limits.max_current3 / 1000.0],
+        }
 
-        logging.info(f"current: {current}, voltage_millis: {voltage}, dac_value: {dac_value}");
-        return dac_value
+    def set_limits(self, max_current):
+        # per-channel current limit in A
+        limits = coms_pb2.QLimits()
+        limits.max_current0, limits.max_current1, limits.max_current2, limits.max_current3 = \
+            [int(1000.0 * c) for c in max_current]
+        self._set(6, limits)
+
+    def get_thermal_limits(self):
+        thermal = self._get(37, coms_pb2.QThermal())
+        return {
+            'warning': thermal.warning / 1000.0,
+            'derate': thermal.derate / 1000.0,
+            'trip': thermal.trip / 1000.0,
+        }
+
+    def set_thermal_limits(self, warning, derate, trip):
+        # temperatures in °C, the setpoints are scaled down linearly between derate and trip
+        thermal = coms_pb2.QThermal()
+        thermal.warning = int(1000.0 * warning)
+        thermal.derate = int(1000.0 * derate)
+        thermal.trip = int(1000.0 * trip)
+        self._set(38, thermal)
+
+    def clear_fault(self):
+        self._list_command(36, "clear_fault")
+
+    def get_soa(self):
+        soa = self._get(40, coms_pb2.QSoa())
+        times = [soa.time0, soa.time1, soa.time2, soa.time3]
+        powers = [soa.power0, soa.power1, soa.power2, soa.power3]
+        return {
+            'points': [(t / 1000.0, p / 1000.0) for t, p in zip(times, powers)],
+            'max_temp': soa.max_temp / 1000.0,
+            'shutdown': soa.shutdown == 1,
+        }
+
+    def set_soa(self, points, max_temp, shutdown=False):
+        # four (pulse duration in s, dissipation per MOSFET in W) tuples sorted by duration,
+        # the powers apply at 25°C and are derated to 0 at max_temp (°C), a violation clamps
+        # the channel or shuts the load down. Durations below the 4 ms sample period are
+        # rejected
+        soa = coms_pb2.QSoa()
+        soa.time0, soa.time1, soa.time2, soa.time3 = [int(1000.0 * t) for t, _ in points]
+        soa.power0, soa.power1, soa.power2, soa.power3 = [int(1000.0 * p) for _, p in points]
+        soa.max_temp = int(1000.0 * max_temp)
+        soa.shutdown = 1 if shutdown else 0
+        self._set(41, soa)
+
+    def get_comm_timeout(self):
+        return self._get(42, coms_pb2.QCommTimeout()).timeout / 1000.0
+
+    def set_comm_timeout(self, timeout):
+        # the load is shut down if no request arrives within timeout (s) while it is on,
+        # a running list or discharge test keeps going, 0 disables it
+        qtimeout = coms_pb2.QCommTimeout()
+        qtimeout.timeout = int(1000.0 * timeout)
+        self._set(43, qtimeout)
+
+    def get_info(self):
+        # reset cause is 0 unknown, 1 power-on or brown-out, 2 reset pin, 3 software or
+        # panic, 4 independent watchdog, 5 window watchdog, 6 low-power
+        # capabilities are the names of the supported modes and features
+        info = self._get(44, coms_pb2.QInfo())
+        return {
+            'reset_cause': info.reset_cause,
+            'protocol_version': info.protocol_version,
+            'firmware_version': info.firmware_version,
+            'git_hash': info.git_hash,
+            'build_date': info.build_date,
+            'hardware_revision': info.hardware_revision,
+            'uid': info.uid.hex().upper(),
+            'num_channels': info.num_channels,
+            'capabilities': [name for bit, name in enumerate(CAPABILITIES) if info.capabilities & (1 << bit)],
+            'max_current': info.max_current / 1000.0,
+            'max_list_steps': info.max_list_steps,
+            'max_sweep_points': info.max_sweep_points,
+            'max_capture_points': info.max_capture_points,
+            'max_message': info.max_message,
+        }
+
+    def get_awd(self):
+        awd = self._get(45, coms_pb2.QAwd())
+        return {
+            'current': awd.current / 1000.0,
+            'voltage_low': awd.voltage_low / 1000.0,
+            'voltage_high': awd.voltage_high / 1000.0,
+        }
+
+    def set_awd(self, current=0, voltage_low=0, voltage_high=0):
+        # hardware trip window, current per channel (A) and input voltage (V), 0 disables
+        # a limit; checked on every scan while the load is on
+        awd = coms_pb2.QAwd()
+        awd.current = int(1000.0 * current)
+        awd.voltage_low = int(1000.0 * voltage_low)
+        awd.voltage_high = int(1000.0 * voltage_high)
+        self._set(46, awd)
+
+    def get_statistics(self):
+        # per channel current (A) and input voltage (V) of every 1 ms scan: rolling average
+        # over the window, min, max and RMS since the last reset
+        results = []
+        for index in range(5):
+            stats = coms_pb2.QStatistics()
+            stats.index = index
+            with self.serial_port_ctrl_lock:
+                resp = self._request(47, stats)
+                if resp is None or resp.error != 0:
+                    raise Exception("failed reading statistics!")
+                stats.ParseFromString(self._payload(resp.data))
+            results.append({
+                'average': stats.average / 1000.0,
+                'min': stats.min / 1000.0,
+                'max': stats.max / 1000.0,
+                'rms': stats.rms / 1000.0,
+                'count': stats.count,
+                'window': stats.window,
+            })
+        return {'current': results[:4], 'voltage': results[4]}
+
+    def reset_statistics(self):
+        self._list_command(48, "reset_statistics")
+
+    def set_statistics_window(self, window):
+        # number of scans (1 to 64) of the rolling average, also resets the statistics
+        qwindow = coms_pb2.QStatisticsWindow()
+        qwindow.window = int(window)
+        self._set(49, qwindow)
+
+    def get_mcu_calibration(self):
+        # factory calibration of the ADC and the resulting reference (V), analog supply (V)
+        # and MCU temperature (°C) next to the board temperature (°C) for a sanity check
+        cal = self._get(55, coms_pb2.QMcuCalibration())
+        return {
+            'vrefint_cal': cal.vrefint_cal,
+            'ts_cal1': cal.ts_cal1,
+            'ts_cal2': cal.ts_cal2,
+            'valid': cal.valid != 0,
+            'vrefint': cal.vrefint / 1000000.0,
+            'vdda': cal.vdda / 1000.0,
+            'mcu_temp': cal.mcu_temp / 1000.0,
+            'board_temp': cal.board_temp / 1000.0,
+        }
+
+    def capture(self, source="manual", edge="rising", level=0, pre_trigger=0, decimation=1):
+        # waveform capture of the input voltage and the total current, one point every
+        # decimation 1 ms scans; level in V for the voltage and A for the current trigger,
+        # load_step triggers on setpoint changes, manual only on trigger_capture()
+        capture = coms_pb2.QCapture()
+        capture.source = ["voltage", "current", "load_step", "manual"].index(source)
+        capture.edge = ["rising", "falling"].index(edge)
+        capture.level = int(1000.0 * level)
+        capture.pre_trigger = pre_trigger
+        capture.decimation = decimation
+        self._set(50, capture)
+
+    def trigger_capture(self):
+        self._list_command(51, "trigger_capture")
+
+    def get_capture_status(self):
+        # state is 0 idle, 1 armed, 2 triggered, 3 done
+        status = self._get(52, coms_pb2.QCaptureStatus())
+        return {
+            'state': status.state,
+            'count': status.count,
+            'pre_trigger': status.pre_trigger,
+            'decimation': status.decimation,
+        }
+
+    def abort_capture(self):
+        self._list_command(54, "abort_capture")
+
+    def get_capture_data(self):
+        # (voltage in V, current in A) of every point, the trigger point is at pre_trigger
+        results = []
+        count = self.get_capture_status()['count']
+        while len(results) < count:
+            data = coms_pb2.QCaptureData()
+            data.offset = len(results)
+            with self.serial_port_ctrl_lock:
+                resp = self._request(53, data)
+                if resp is None or resp.error != 0:
+                    raise Exception("failed reading capture data!")
+                data.ParseFromString(self._payload(resp.data))
+            for voltage, current in struct.iter_unpack('<ii', data.data):
+                results.append((voltage / 1000.0, current / 1000.0))
+        return results
+
+    def heartbeat(self):
+        # any request resets the communication timeout, this one does nothing else
+        self._list_command(0, "heartbeat")
+
+    def get_soa_status(self):
+        # state is 0 ok, 1 channel clamped, 2 load shut down, point indexes the SOA table
+        status = self._get(39, coms_pb2.QSoaStatus())
+        return {
+            'state': status.state,
+            'channel': status.channel,
+            'point': status.point,
+            'power': status.power / 1000.0,
+            'limit': status.limit / 1000.0,
+        }
+
+    def get_fan_curve(self):
+        curve = self._get(7, coms_pb2.QFanCurve())
+        temps = [curve.temp0, curve.temp1, curve.temp2, curve.temp3]
+        pwms = [curve.pwm0, curve.pwm1, curve.pwm2, curve.pwm3]
+        return [(t / 1000.0, p / 100.0) for t, p in zip(temps, pwms)]
+
+    def set_fan_curve(self, points):
+        # list of four (temperature in °C, fan speed 0..1) tuples
+        curve = coms_pb2.QFanCurve()
+        curve.temp0, curve.temp1, curve.temp2, curve.temp3 = [int(1000.0 * t) for t, _ in points]
+        curve.pwm0, curve.pwm1, curve.pwm2, curve.pwm3 = [int(100.0 * p) for _, p in points]
+        self._set(8, curve)
+
+    def get_defaults(self):
+        defaults = self._get(9, coms_pb2.QDefaults())
+        return {
+            'sdn': defaults.sdn == 1,
+            'pwm': defaults.pwm / 100.0,
+            'current': [defaults.current0 / 1000.0, defaults.current1 / 1000.0,
+                        defaults.current2 / 1000.0, defaults.current3 / 1000.0],
+        }
+
+    def set_defaults(self, sdn, pwm, current):
+        defaults = coms_pb2.QDefaults()
+        defaults.sdn = 1 if sdn else 0
+        defaults.pwm = int(100.0 * pwm)
+        defaults.current0, defaults.current1, defaults.current2, defaults.current3 = \
+            [int(1000.0 * c) for c in current]
+        self._set(10, defaults)
+
+    def factory_reset(self):
+        with self.serial_port_ctrl_lock:
+            if self._request(11, None).error != 0:
+                raise Exception("error factory_reset")
 
     def set_shutdown(self, shutdown):
         self.control.sdn = 1 if shutdown else 0
         self._send_control()
 
     def set_current(self, current):
-        self.control.dac0 = self._current_to_dac(current)
-        self.control.dac1 = self._current_to_dac(current)
-        self.control.dac2 = self._current_to_dac(current)
-        self.control.dac3 = self._current_to_dac(current)
+        # total current is split evenly over all channels
+        per_channel = current / NUM_CHANNELS
+        logging.info(f"current: {current}, per channel: {per_channel}")
+        self.control.current0 = per_channel
+        self.control.current1 = per_channel
+        self.control.current2 = per_channel
+        self.control.current3 = per_channel
         self._send_control()
 
-    def _adc_to_current(self, v):
-        r_sense = 0.004 # 4mR
-        r1 = 31600.0 # 31.6k
-        r2 = 1000.0 # 1k
-        return v / r_sense / (1.0 + r1 / r2)
-
-    def _calc_ampere(self, sample, cal):
-        voltage = float(sample) * VREFINT / float(cal);
-        return self._adc_to_current(voltage)
-
-    def _calc_voltage(self, sample, cal):
-        r1 = 33000.0
-        r2 = 10000.0
-        voltage = float(sample) * VREFINT / float(cal) * (r1 + r2) / r2;
-        return voltage
-
     def _receive_state(self):
         with self.serial_port_ctrl_lock:
             resp = self._request(2, None)
@@ -171,22 +726,91 @@ class ELoad:
                 raise Exception("failed reading status!")
 
             status = coms_pb2.QState()
-            status.ParseFromString(resp.data[1:])
-
-            self.state.ch0 = self._calc_ampere(status.ch0, status.cal)
-            self.state.ch1 = self._calc_ampere(status.ch1, status.cal)
-            self.state.ch2 = self._calc_ampere(status.ch2, status.cal)
-            self.state.ch3 = self._calc_ampere(status.ch3, status.cal)
-            self.state.cal = status.cal
-            self.state.v = self._calc_voltage(status.v, status.cal)
-            self.state.p = self.state.v * (self.state.ch0 + self.state.ch1 + self.state.ch2 + self.state.ch3)
-            self.state.temp = status.temp * 0.0625
-            self.state.sdn = True if status.sdn == 1 else False
+            status.ParseFromString(self._payload(resp.data))
+        self._update_state(status)
+
+    def _update_state(self, status):
+        # the firmware converts the samples, currents in A, voltage in V, power in W
+        self.state.ch0 = status.current0 / 1000.0
+        self.state.ch1 = status.current1 / 1000.0
+        self.state.ch2 = status.current2 / 1000.0
+        self.state.ch3 = status.current3 / 1000.0
+        self.state.v = status.voltage / 1000.0
+        self.state.p = status.power / 1000.0
+        self.state.temp = status.temp / 1000.0
+        self.state.sdn = True if status.sdn == 1 else False
+        self.state.mode = status.mode
+        self.state.list_state = status.list_state
+        self.state.list_step = status.list_step
+        self.state.list_loop = status.list_loop
+        self.state.thermal = status.thermal
+        self.state.fault = status.fault
 
     def get_state(self):
         self._receive_state()
         return self.state.to_dict()
 
+    def subscribe(self, interval=0.1, events=True):
+        # the device pushes the state every interval seconds (0 disables it, 0.01 to 60 s)
+        # and, with events set, every fault, mode, list, thermal, SOA and shutdown change
+        # as it happens; read them with read_events()
+        sub = coms_pb2.QSubscribe()
+        sub.interval = int(round(1000.0 * interval))
+        sub.events = 1 if events else 0
+        self._set(57, sub)
+        self.events.clear()
+
+    def read_events(self, timeout=1.0):
+        # waits up to timeout seconds for the first push and returns all received ones,
+        # oldest first, as ('state', state dict) or ('event', event dict), time in s since boot
+        with self.serial_port_ctrl_lock:
+            saved = self._serial_port_ctrl.timeout
+            deadline = time.monotonic() + timeout
+            try:
+                while not self.events and time.monotonic() < deadline:
+                    self._serial_port_ctrl.timeout = deadline - time.monotonic()
+                    self._read_response()
+                # what else already arrived
+                self._serial_port_ctrl.timeout = 0
+                while self._serial_port_ctrl.in_waiting:
+                    self._read_response()
+            finally:
+                self._serial_port_ctrl.timeout = saved
+
+            pushes = []
+            while self.events:
+                push = self.events.popleft()
+                if push.kind == MSG_STATE:
+                    status = coms_pb2.QState()
+                    status.ParseFromString(self._payload(push.data))
+                    self._update_state(status)
+                    pushes.append(('state', self.state.to_dict()))
+                elif push.kind == MSG_EVENT:
+                    event = coms_pb2.QEvent()
+                    event.ParseFromString(self._payload(push.data))
+                    name = EVENTS[event.event] if 0 <= event.event < len(EVENTS) else str(event.event)
+                    pushes.append(('event', {
+                        'event': name,
+                        'value': event.value,
+                        'time': event.time / 1000.0,
+                    }))
+            return pushes
+
+    def get_raw_state(self):
+        # raw ADC codes of the last sample (15 bits, 8x oversampled) and the raw reading of
+        # the board temperature sensor, for diagnostics
+        state = self._get(56, coms_pb2.QRawState())
+        return {
+            'ch0': state.ch0,
+            'ch1': state.ch1,
+            'ch2': state.ch2,
+            'ch3': state.ch3,
+            'cal': state.cal,
+            'v': state.v,
+            'temp': state.temp,
+            'ts': state.ts,
+        }
+
     def get_control(self):
         return self.control.to_dict()
 
@@ -195,19 +819,19 @@ class ELoad:
             qcontrol = coms_pb2.QControl()
             qcontrol.sdn = 1 if self.control.sdn else 0
             qcontrol.pwm = int(100.0 * float(self.control.pwm))
-            qcontrol.dac0 = self.control.dac0
-            qcontrol.dac1 = self.control.dac1
-            qcontrol.dac2 = self.control.dac2
-            qcontrol.dac3 = self.control.dac3
+            qcontrol.current0 = int(1000.0 * self.control.current0)
+            qcontrol.current1 = int(1000.0 * self.control.current1)
+            qcontrol.current2 = int(1000.0 * self.control.current2)
+            qcontrol.current3 = int(1000.0 * self.control.current3)
             if self._request(1, qcontrol).error != 0:
                 raise Exception("error send_control")
 
     def shutdown(self):
         logging.info("shutdown ...")
-        self.control.dac0 = 0
-        self.control.dac1 = 0
-        self.control.dac2 = 0
-        self.control.dac3 = 0
+        self.control.current0 = 0
+        self.control.current1 = 0
+        self.control.current2 = 0
+        self.control.current3 = 0
         self.control.sdn = True
         self.control.pwm = 0
         self._send_control()